@@ -0,0 +1,654 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    path::PathBuf,
+};
+
+use bevy::{
+    app::{App, Plugin, PreUpdate, Update},
+    input::{
+        gamepad::{
+            Gamepad, GamepadAxis, GamepadAxisType, GamepadButton, GamepadButtonType, Gamepads,
+        },
+        keyboard::KeyCode,
+        Axis, ButtonInput, InputSystem,
+    },
+    log::{error, info},
+    prelude::{
+        DetectChanges, IntoSystemConfigs, IntoSystemSetConfigs, Res, ResMut, Resource, SystemSet,
+    },
+    reflect::Reflect,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::LaunchOptions,
+    player::{PlayerIndex, MAX_PLAYERS},
+    state::GameMode,
+};
+
+/// where bindings are read from and saved to, unless `--bindings` says otherwise
+pub const DEFAULT_BINDINGS_FILE: &str = "bindings.ron";
+
+/// Something a player wants to do, whatever the button they pressed for it
+///
+/// Actions of each player are in [`PlayerActions`], `Res<ButtonInput<Action>>` has those of every
+/// player in the run at once, for menus and anything not tied to a cannon.
+#[derive(
+    Serialize, Deserialize, Reflect, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    /// load the next hand type of the ruleset
+    CycleHand,
+    /// load the hand type of this rank in the ruleset
+    SelectHand(usize),
+    Pause,
+    /// validate the focused menu entry
+    Confirm,
+    /// leave the current menu
+    Back,
+    ToggleDebug,
+    ToggleInspector,
+}
+
+/// how far a stick must be pushed along an axis before its action is pressed
+const STICK_PRESS: f32 = 0.5;
+/// a pressed stick action is released under this, so a stick resting near the threshold does
+/// not flicker
+const STICK_RELEASE: f32 = 0.35;
+
+/// A physical button
+///
+/// With a single player every connected gamepad is read, otherwise each player has their own.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    Key(KeyCode),
+    Pad(GamepadButtonType),
+    /// a stick pushed along an axis, towards its positive values or not
+    PadAxis {
+        axis: GamepadAxisType,
+        positive: bool,
+    },
+}
+
+impl Binding {
+    /// bindings of the same kind replace each other when rebinding
+    fn same_device(self, other: Self) -> bool {
+        std::mem::discriminant(&self) == std::mem::discriminant(&other)
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Key(key) => write!(f, "{key:?}"),
+            Self::Pad(button) => write!(f, "pad {button:?}"),
+            Self::PadAxis { axis, positive } => {
+                write!(f, "pad {axis:?}{}", if *positive { '+' } else { '-' })
+            }
+        }
+    }
+}
+
+/// Buttons triggering each [`Action`] of one player
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct PlayerBindings(BTreeMap<Action, Vec<Binding>>);
+
+/// Bindings of every player, as written in the bindings file
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct Bindings(Vec<PlayerBindings>);
+
+/// Keys of the actions every player has, so players can share a keyboard
+struct KeyboardLayout {
+    up: KeyCode,
+    down: KeyCode,
+    left: KeyCode,
+    right: KeyCode,
+    fire: KeyCode,
+    cycle_hand: KeyCode,
+}
+
+const KEYBOARD_LAYOUTS: [KeyboardLayout; MAX_PLAYERS] = [
+    KeyboardLayout {
+        up: KeyCode::ArrowUp,
+        down: KeyCode::ArrowDown,
+        left: KeyCode::ArrowLeft,
+        right: KeyCode::ArrowRight,
+        fire: KeyCode::Space,
+        cycle_hand: KeyCode::KeyA,
+    },
+    KeyboardLayout {
+        up: KeyCode::KeyI,
+        down: KeyCode::KeyK,
+        left: KeyCode::KeyJ,
+        right: KeyCode::KeyL,
+        fire: KeyCode::KeyO,
+        cycle_hand: KeyCode::KeyU,
+    },
+];
+
+/// Digit keys select the hand type of the same rank
+const SELECT_HAND_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+impl PlayerBindings {
+    fn defaults(player: PlayerIndex) -> Self {
+        use Binding::{Key, Pad};
+        let stick = |axis, positive| Binding::PadAxis { axis, positive };
+        let keys = &KEYBOARD_LAYOUTS[player.0 % MAX_PLAYERS];
+        let mut bindings = BTreeMap::from([
+            (
+                Action::MoveUp,
+                vec![
+                    Key(keys.up),
+                    Pad(GamepadButtonType::DPadUp),
+                    stick(GamepadAxisType::LeftStickY, true),
+                ],
+            ),
+            (
+                Action::MoveDown,
+                vec![
+                    Key(keys.down),
+                    Pad(GamepadButtonType::DPadDown),
+                    stick(GamepadAxisType::LeftStickY, false),
+                ],
+            ),
+            (
+                Action::MoveLeft,
+                vec![
+                    Key(keys.left),
+                    Pad(GamepadButtonType::DPadLeft),
+                    stick(GamepadAxisType::LeftStickX, false),
+                ],
+            ),
+            (
+                Action::MoveRight,
+                vec![
+                    Key(keys.right),
+                    Pad(GamepadButtonType::DPadRight),
+                    stick(GamepadAxisType::LeftStickX, true),
+                ],
+            ),
+            (
+                Action::Fire,
+                vec![Key(keys.fire), Pad(GamepadButtonType::South)],
+            ),
+            (
+                Action::CycleHand,
+                vec![Key(keys.cycle_hand), Pad(GamepadButtonType::West)],
+            ),
+            (Action::Pause, vec![Pad(GamepadButtonType::Start)]),
+            (Action::Confirm, vec![Pad(GamepadButtonType::South)]),
+            (Action::Back, vec![Pad(GamepadButtonType::East)]),
+        ]);
+        // keys outside of the layouts all go to the first player
+        if player.0 == 0 {
+            let mut add = |action, key| bindings.entry(action).or_default().push(Key(key));
+            add(Action::Pause, KeyCode::Escape);
+            add(Action::Pause, KeyCode::KeyP);
+            add(Action::Confirm, KeyCode::Enter);
+            add(Action::Confirm, KeyCode::Space);
+            add(Action::Back, KeyCode::Escape);
+            add(Action::ToggleDebug, KeyCode::F12);
+            add(Action::ToggleInspector, KeyCode::Equal);
+            for (rank, key) in SELECT_HAND_KEYS.into_iter().enumerate() {
+                add(Action::SelectHand(rank), key);
+            }
+        }
+        Self(bindings)
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self(
+            (0..MAX_PLAYERS)
+                .map(|player| PlayerBindings::defaults(PlayerIndex(player)))
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum BindingsError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Write(ron::Error),
+}
+
+impl fmt::Display for BindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not access bindings file: {err}"),
+            Self::Parse(err) => write!(f, "could not parse bindings: {err}"),
+            Self::Write(err) => write!(f, "could not serialize bindings: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for BindingsError {}
+
+impl From<std::io::Error> for BindingsError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for BindingsError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl From<ron::Error> for BindingsError {
+    fn from(err: ron::Error) -> Self {
+        Self::Write(err)
+    }
+}
+
+impl Bindings {
+    /// Players and actions missing from the file keep their default bindings
+    ///
+    /// # Errors
+    /// if the file can't be read or is not valid
+    pub fn load(path: &PathBuf) -> Result<Self, BindingsError> {
+        let mut bindings: Self = ron::de::from_bytes(&std::fs::read(path)?)?;
+        bindings.0.truncate(MAX_PLAYERS);
+        for (player, defaults) in Self::default().0.into_iter().enumerate() {
+            if player == bindings.0.len() {
+                bindings.0.push(PlayerBindings::default());
+            }
+            for (action, default) in defaults.0 {
+                bindings.0[player].0.entry(action).or_insert(default);
+            }
+        }
+        Ok(bindings)
+    }
+
+    /// # Errors
+    /// if the file can't be written
+    pub fn save(&self, path: &PathBuf) -> Result<(), BindingsError> {
+        let pretty = ron::ser::PrettyConfig::default();
+        Ok(std::fs::write(
+            path,
+            ron::ser::to_string_pretty(self, pretty)?,
+        )?)
+    }
+
+    pub fn get(&self, player: PlayerIndex, action: Action) -> &[Binding] {
+        self.0
+            .get(player.0)
+            .and_then(|bindings| bindings.0.get(&action))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// `binding` replaces the bindings of `action` on the same device, the other device keeps its own
+    pub fn rebind(&mut self, player: PlayerIndex, action: Action, binding: Binding) {
+        let Some(player_bindings) = self.0.get_mut(player.0) else {
+            return;
+        };
+        let bindings = player_bindings.0.entry(action).or_default();
+        bindings.retain(|other| !other.same_device(binding));
+        bindings.push(binding);
+    }
+
+    /// e.g. `Space / pad South`
+    pub fn describe(&self, player: PlayerIndex, action: Action) -> String {
+        let names: Vec<_> = self
+            .get(player, action)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        if names.is_empty() {
+            "unbound".into()
+        } else {
+            names.join(" / ")
+        }
+    }
+}
+
+/// File the [`Bindings`] are saved to whenever they change
+#[derive(Resource, Debug)]
+struct BindingsFile(PathBuf);
+
+/// Actions of each player, whether they are in the run or not
+#[derive(Resource, Debug, Clone)]
+pub struct PlayerActions(Vec<ButtonInput<Action>>);
+
+impl Default for PlayerActions {
+    fn default() -> Self {
+        Self(vec![ButtonInput::default(); MAX_PLAYERS])
+    }
+}
+
+impl PlayerActions {
+    #[must_use]
+    pub fn get(&self, player: PlayerIndex) -> &ButtonInput<Action> {
+        &self.0[player.0]
+    }
+
+    pub fn get_mut(&mut self, player: PlayerIndex) -> Option<&mut ButtonInput<Action>> {
+        self.0.get_mut(player.0)
+    }
+
+    /// forgets what was just pressed and released by every player, see [`ButtonInput::clear`]
+    pub fn clear(&mut self) {
+        for actions in &mut self.0 {
+            actions.clear();
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (PlayerIndex, &ButtonInput<Action>)> {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(player, actions)| (PlayerIndex(player), actions))
+    }
+}
+
+/// Actions of each player that stay released until their buttons are let go
+#[derive(Resource, Debug)]
+pub struct HeldActions {
+    requested: bool,
+    held: Vec<HashSet<Action>>,
+}
+
+impl Default for HeldActions {
+    fn default() -> Self {
+        Self {
+            requested: false,
+            held: vec![HashSet::new(); MAX_PLAYERS],
+        }
+    }
+}
+
+impl HeldActions {
+    /// actions pressed so far are released on the next frame, and ignored until pressed again,
+    /// so a button confirming a menu does not also fire in the game that follows
+    pub const fn hold(&mut self) {
+        self.requested = true;
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ActionSystems {
+    /// fills [`PlayerActions`] from the keyboard and gamepads
+    Read,
+    /// fills `ButtonInput<Action>` from the [`PlayerActions`]
+    Merge,
+}
+
+struct Devices<'a> {
+    keys: &'a ButtonInput<KeyCode>,
+    pads: &'a [Gamepad],
+    pad_buttons: &'a ButtonInput<GamepadButton>,
+    pad_axes: &'a Axis<GamepadAxis>,
+}
+
+impl Devices<'_> {
+    /// `stick_threshold` is how far sticks must be pushed
+    fn pressed(&self, binding: Binding, stick_threshold: f32) -> bool {
+        match binding {
+            Binding::Key(key) => self.keys.pressed(key),
+            Binding::Pad(button_type) => self.pads.iter().any(|gamepad| {
+                self.pad_buttons
+                    .pressed(GamepadButton::new(*gamepad, button_type))
+            }),
+            Binding::PadAxis { axis, positive } => self.pads.iter().any(|gamepad| {
+                let value = self
+                    .pad_axes
+                    .get(GamepadAxis::new(*gamepad, axis))
+                    .unwrap_or_default();
+                let value = if positive { value } else { -value };
+                value >= stick_threshold
+            }),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_player_actions(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    pad_buttons: Res<ButtonInput<GamepadButton>>,
+    pad_axes: Res<Axis<GamepadAxis>>,
+    bindings: Res<Bindings>,
+    mode: Res<GameMode>,
+    mut players: ResMut<PlayerActions>,
+    mut held: ResMut<HeldActions>,
+) {
+    if held.requested {
+        held.requested = false;
+        for (held, actions) in held.held.iter_mut().zip(&players.0) {
+            held.extend(actions.get_pressed());
+        }
+    }
+    // gamepads go to players in the order they were connected
+    let mut pads: Vec<Gamepad> = gamepads.iter().collect();
+    pads.sort_by_key(|gamepad| gamepad.id);
+    let shared_pads = mode.players() == 1;
+
+    let players = players.0.iter_mut().zip(&mut held.held);
+    for (player, ((actions, held), player_bindings)) in players.zip(&bindings.0).enumerate() {
+        let devices = Devices {
+            keys: &keys,
+            pads: if shared_pads {
+                &pads
+            } else {
+                pads.get(player..=player).unwrap_or_default()
+            },
+            pad_buttons: &pad_buttons,
+            pad_axes: &pad_axes,
+        };
+        actions.clear();
+        for (action, action_bindings) in &player_bindings.0 {
+            let stick_threshold = if actions.pressed(*action) {
+                STICK_RELEASE
+            } else {
+                STICK_PRESS
+            };
+            let down = action_bindings
+                .iter()
+                .any(|binding| devices.pressed(*binding, stick_threshold));
+            if !down {
+                held.remove(action);
+            }
+            if down && !held.contains(action) {
+                actions.press(*action);
+            } else {
+                actions.release(*action);
+            }
+        }
+    }
+}
+
+/// Players outside of the run can't use the menus either
+fn merge_actions(
+    mode: Res<GameMode>,
+    players: Res<PlayerActions>,
+    mut actions: ResMut<ButtonInput<Action>>,
+) {
+    actions.clear();
+    let pressed: HashSet<Action> = players
+        .0
+        .iter()
+        .take(mode.players())
+        .flat_map(ButtonInput::get_pressed)
+        .copied()
+        .collect();
+    let released: Vec<Action> = actions
+        .get_pressed()
+        .filter(|action| !pressed.contains(action))
+        .copied()
+        .collect();
+    for action in released {
+        actions.release(action);
+    }
+    for action in pressed {
+        actions.press(action);
+    }
+}
+
+fn save_bindings(bindings: Res<Bindings>, file: Res<BindingsFile>) {
+    if bindings.is_added() || !bindings.is_changed() {
+        return;
+    }
+    match bindings.save(&file.0) {
+        Ok(()) => info!("bindings saved to {}", file.0.display()),
+        Err(err) => error!("{err}"),
+    }
+}
+
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut App) {
+        let path = app
+            .world()
+            .get_resource::<LaunchOptions>()
+            .and_then(|options| options.bindings.clone())
+            .unwrap_or_else(|| DEFAULT_BINDINGS_FILE.into());
+        let bindings = match Bindings::load(&path) {
+            Ok(bindings) => bindings,
+            Err(BindingsError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => {
+                Bindings::default()
+            }
+            Err(err) => {
+                error!("{}: {err}, using default bindings", path.display());
+                Bindings::default()
+            }
+        };
+
+        app.register_type::<Action>()
+            .insert_resource(bindings)
+            .insert_resource(BindingsFile(path))
+            .init_resource::<ButtonInput<Action>>()
+            .init_resource::<PlayerActions>()
+            .init_resource::<HeldActions>()
+            .configure_sets(
+                PreUpdate,
+                (ActionSystems::Read, ActionSystems::Merge)
+                    .chain()
+                    .after(InputSystem),
+            )
+            .add_systems(
+                PreUpdate,
+                (
+                    update_player_actions.in_set(ActionSystems::Read),
+                    merge_actions.in_set(ActionSystems::Merge),
+                ),
+            )
+            .add_systems(Update, save_bindings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// bindings read back from `source`, written to a file of its own
+    fn load(name: &str, source: &str) -> Result<Bindings, BindingsError> {
+        let path = std::env::temp_dir().join(format!("rps-{name}-{}.ron", std::process::id()));
+        std::fs::write(&path, source).unwrap();
+        let bindings = Bindings::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        bindings
+    }
+
+    #[test]
+    fn missing_actions_and_players_keep_their_defaults() {
+        let bindings = load("partial", "[{Fire: [Key(KeyX)]}]").unwrap();
+        let defaults = Bindings::default();
+
+        let player = PlayerIndex(0);
+        assert_eq!(
+            bindings.get(player, Action::Fire),
+            &[Binding::Key(KeyCode::KeyX)]
+        );
+        assert_eq!(
+            bindings.get(player, Action::MoveUp),
+            defaults.get(player, Action::MoveUp)
+        );
+        assert_eq!(bindings.0[1], defaults.0[1]);
+        assert_eq!(bindings.0.len(), MAX_PLAYERS);
+    }
+
+    #[test]
+    fn extra_players_are_dropped() {
+        let players = ["{}"; MAX_PLAYERS + 2].join(", ");
+        let bindings = load("extra", &format!("[{players}]")).unwrap();
+        assert_eq!(bindings, Bindings::default());
+    }
+
+    #[test]
+    fn invalid_files_are_errors() {
+        assert!(matches!(
+            load("invalid", "[{Fire: ["),
+            Err(BindingsError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn rebinding_replaces_only_the_same_device() {
+        let mut bindings = Bindings::default();
+        let player = PlayerIndex(0);
+        bindings.rebind(player, Action::Fire, Binding::Key(KeyCode::KeyX));
+        assert_eq!(
+            bindings.get(player, Action::Fire),
+            &[
+                Binding::Pad(GamepadButtonType::South),
+                Binding::Key(KeyCode::KeyX)
+            ]
+        );
+
+        bindings.rebind(player, Action::Fire, Binding::Pad(GamepadButtonType::North));
+        assert_eq!(
+            bindings.get(player, Action::Fire),
+            &[
+                Binding::Key(KeyCode::KeyX),
+                Binding::Pad(GamepadButtonType::North)
+            ]
+        );
+        // the other player keeps their own
+        assert_eq!(
+            bindings.get(PlayerIndex(1), Action::Fire),
+            Bindings::default().get(PlayerIndex(1), Action::Fire)
+        );
+    }
+
+    #[test]
+    fn sticks_and_buttons_are_different_devices() {
+        let mut bindings = Bindings::default();
+        let player = PlayerIndex(0);
+        bindings.rebind(
+            player,
+            Action::MoveUp,
+            Binding::Pad(GamepadButtonType::North),
+        );
+        assert!(bindings
+            .get(player, Action::MoveUp)
+            .contains(&Binding::PadAxis {
+                axis: GamepadAxisType::LeftStickY,
+                positive: true
+            }));
+        assert!(!bindings
+            .get(player, Action::MoveUp)
+            .contains(&Binding::Pad(GamepadButtonType::DPadUp)));
+    }
+}
@@ -0,0 +1,190 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{
+    app::{App, Plugin, PreUpdate, Update},
+    asset::Assets,
+    color::Color,
+    core::Name,
+    input::{mouse::MouseButton, touch::Touches, ButtonInput},
+    math::{IVec3, Quat, Vec2},
+    prelude::{
+        in_state, Annulus, Camera, Commands, Component, DetectChangesMut, GlobalTransform,
+        IntoSystemConfigs, Mesh, OnEnter, Query, Res, ResMut, Resource, StateScoped, Transform,
+        Visibility, With,
+    },
+    reflect::Reflect,
+    sprite::{ColorMaterial, MaterialMesh2dBundle, Mesh2dHandle},
+    window::{PrimaryWindow, Window},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::ActionSystems,
+    hand_cannon::{Facing, HandCannon},
+    player::PlayerIndex,
+    state::{GameState, InGame},
+};
+
+/// How the cannon is aimed
+#[derive(Resource, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AimMode {
+    /// straight up
+    #[default]
+    Fixed,
+    /// toward the cursor or touch point, which also move and fire the cannon when held
+    Pointer,
+}
+
+/// Cursor or touch point, in world coordinates
+///
+/// Recorded in replays along with the actions.
+#[derive(Resource, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct PointerInput {
+    pub position: Option<Vec2>,
+    /// the mouse button or a finger is down
+    pub held: bool,
+}
+
+/// the cannon never aims further than this from straight up, in radians
+const MAX_AIM_ANGLE: f32 = FRAC_PI_2 * 0.8;
+/// holding the pointer less than this above the cannon moves it instead of firing
+const TOUCH_MOVE_ZONE: f32 = 50.0;
+/// horizontal distance from the cannon under which holding the pointer does not move it
+const TOUCH_MOVE_THRESHOLD: f32 = 50.0;
+
+impl PointerInput {
+    /// grid move toward the pointer, when held at the height of the cannon
+    #[must_use]
+    pub fn move_direction(&self, cannon: Vec2) -> IVec3 {
+        match self.position {
+            Some(position) if self.held && position.y < cannon.y + TOUCH_MOVE_ZONE => {
+                let offset = position.x - cannon.x;
+                if offset > TOUCH_MOVE_THRESHOLD {
+                    IVec3::X
+                } else if offset < -TOUCH_MOVE_THRESHOLD {
+                    IVec3::NEG_X
+                } else {
+                    IVec3::ZERO
+                }
+            }
+            _ => IVec3::ZERO,
+        }
+    }
+
+    /// held above the cannon
+    #[must_use]
+    pub fn fires(&self, cannon: Vec2) -> bool {
+        self.held
+            && self
+                .position
+                .is_some_and(|position| position.y >= cannon.y + TOUCH_MOVE_ZONE)
+    }
+
+    /// rotation of a cannon at `cannon` aiming at the pointer, clamped to the upper half
+    #[must_use]
+    pub fn aim(&self, cannon: Vec2) -> Option<Quat> {
+        let direction = (self.position? - cannon).try_normalize()?;
+        let angle = Vec2::Y
+            .angle_between(direction)
+            .clamp(-MAX_AIM_ANGLE, MAX_AIM_ANGLE);
+        Some(Quat::from_rotation_z(angle))
+    }
+}
+
+/// Touches win over the mouse, aiming with them needs [`AimMode::Pointer`] in the settings
+fn read_pointer(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    mut pointer: ResMut<PointerInput>,
+) {
+    let (Ok(window), Some((camera, camera_transform))) =
+        (windows.get_single(), cameras.iter().next())
+    else {
+        return;
+    };
+    let touch = touches.first_pressed_position();
+    let held = touch.is_some() || mouse.pressed(MouseButton::Left);
+    let screen = touch.or_else(|| window.cursor_position());
+    let position = screen.and_then(|screen| camera.viewport_to_world_2d(camera_transform, screen));
+    pointer.set_if_neq(PointerInput { position, held });
+}
+
+/// Only the first player aims with the pointer, the others always fire straight ahead
+fn aim_cannons(
+    mode: Res<AimMode>,
+    pointer: Res<PointerInput>,
+    mut cannons: Query<(&PlayerIndex, &Facing, &mut Transform), With<HandCannon>>,
+) {
+    for (player, facing, mut transform) in &mut cannons {
+        let aim = match *mode {
+            AimMode::Pointer if player.0 == 0 => pointer.aim(transform.translation.truncate()),
+            _ => None,
+        };
+        transform.rotation = facing.rotation() * aim.unwrap_or(Quat::IDENTITY);
+    }
+}
+
+/// Shows where the cannon aims in [`AimMode::Pointer`]
+#[derive(Component, Reflect)]
+struct Reticle;
+
+const RETICLE_RADIUS: f32 = 14.0;
+const RETICLE_THICKNESS: f32 = 3.0;
+
+fn spawn_reticle(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let mesh = Mesh2dHandle(meshes.add(Annulus::new(
+        RETICLE_RADIUS - RETICLE_THICKNESS,
+        RETICLE_RADIUS,
+    )));
+    commands.spawn((
+        Name::new("Reticle"),
+        StateScoped(InGame),
+        Reticle,
+        MaterialMesh2dBundle {
+            mesh,
+            material: materials.add(Color::srgba(1.0, 1.0, 1.0, 0.8)),
+            transform: Transform::from_xyz(0.0, 0.0, 1.0),
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+    ));
+}
+
+fn move_reticle(
+    mode: Res<AimMode>,
+    pointer: Res<PointerInput>,
+    mut reticles: Query<(&mut Transform, &mut Visibility), With<Reticle>>,
+) {
+    for (mut transform, mut visibility) in &mut reticles {
+        match pointer.position.filter(|_| *mode == AimMode::Pointer) {
+            Some(position) => {
+                transform.translation = position.extend(transform.translation.z);
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+pub struct AimPlugin;
+
+impl Plugin for AimPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<AimMode>()
+            .register_type::<Reticle>()
+            .init_resource::<AimMode>()
+            .init_resource::<PointerInput>()
+            .add_systems(PreUpdate, read_pointer.in_set(ActionSystems::Read))
+            .add_systems(OnEnter(InGame), spawn_reticle)
+            .add_systems(
+                Update,
+                (aim_cannons, move_reticle).run_if(in_state(GameState::Playing)),
+            );
+    }
+}
@@ -2,12 +2,17 @@ use bevy::{
     app::{App, Update},
     asset::Handle,
     math::Vec3,
-    prelude::{Bundle, Component, Deref, DerefMut, Image, Plugin, Query, Res, Transform},
+    prelude::{
+        in_state, not, Bundle, Component, Deref, DerefMut, Image, IntoSystemConfigs, Plugin, Query,
+        Res, Transform,
+    },
     reflect::Reflect,
     sprite::{SpriteBundle, TextureAtlas, TextureAtlasLayout},
     time::{Time, Timer, TimerMode},
 };
 
+use crate::state::GameState;
+
 #[derive(Component, Reflect, Clone, Debug)]
 pub struct AnimationIndices {
     pub first: usize,
@@ -15,6 +20,7 @@ pub struct AnimationIndices {
 }
 
 impl AnimationIndices {
+    #[must_use]
     pub const fn from_frames(frames: usize) -> Self {
         Self {
             first: 0,
@@ -22,6 +28,7 @@ impl AnimationIndices {
         }
     }
 
+    #[must_use]
     pub const fn advance(&self, index: usize) -> usize {
         if index >= self.last {
             self.first
@@ -47,6 +54,7 @@ pub fn animate_sprites(
 pub struct AnimationTimer(Timer);
 
 impl AnimationTimer {
+    #[must_use]
     pub fn repeating(duration: f32) -> Self {
         Self(Timer::from_seconds(duration, TimerMode::Repeating))
     }
@@ -61,6 +69,7 @@ pub struct AnimatableSpriteBundle {
 }
 
 impl AnimatableSpriteBundle {
+    #[must_use]
     pub fn new(
         position: Vec3,
         scale: Vec3,
@@ -92,6 +101,9 @@ impl Plugin for AnimationsPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<AnimationTimer>()
             .register_type::<Image>()
-            .add_systems(Update, animate_sprites);
+            .add_systems(
+                Update,
+                animate_sprites.run_if(not(in_state(GameState::Paused))),
+            );
     }
 }
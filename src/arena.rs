@@ -0,0 +1,65 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    core_pipeline::core_2d::Camera2d,
+    math::{Rect, Vec2},
+    prelude::{
+        resource_changed, IntoSystemConfigs, OrthographicProjection, Query, Res, Resource, With,
+    },
+    reflect::Reflect,
+    render::camera::ScalingMode,
+};
+
+/// Playable area, in world coordinates
+///
+/// The camera always shows all of it, and nothing controlled by the player may leave it.
+#[derive(Resource, Reflect, Debug, Clone, Copy)]
+pub struct Arena {
+    pub rect: Rect,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self {
+            rect: Rect::from_center_size(Vec2::ZERO, Vec2::new(1200.0, 700.0)),
+        }
+    }
+}
+
+impl Arena {
+    /// closest position to `point` where a box of `half_size` fits entirely inside the arena
+    #[must_use]
+    pub fn clamp(&self, point: Vec2, half_size: Vec2) -> Vec2 {
+        let min = self.rect.min + half_size;
+        let max = (self.rect.max - half_size).max(min);
+        point.clamp(min, max)
+    }
+}
+
+/// empty space shown around the arena
+const CAMERA_MARGIN: f32 = 40.0;
+
+fn fit_camera_to_arena(
+    arena: Res<Arena>,
+    mut projections: Query<&mut OrthographicProjection, With<Camera2d>>,
+) {
+    let size = arena.rect.size() + 2.0 * CAMERA_MARGIN;
+    for mut projection in &mut projections {
+        projection.scaling_mode = ScalingMode::AutoMin {
+            min_width: size.x,
+            min_height: size.y,
+        };
+    }
+}
+
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Arena>()
+            .init_resource::<Arena>()
+            .add_systems(
+                Update,
+                fit_camera_to_arena.run_if(resource_changed::<Arena>),
+            );
+    }
+}
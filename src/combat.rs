@@ -0,0 +1,234 @@
+use std::collections::HashSet;
+
+use bevy::{
+    app::{App, FixedUpdate, Plugin, Update},
+    math::Vec3,
+    prelude::{
+        in_state, Commands, Component, Entity, Event, EventReader, EventWriter, IntoSystemConfigs,
+        Query, Res, Resource, StateScoped, Transform, Without,
+    },
+    reflect::Reflect,
+};
+use bevy_hanabi::ParticleEffectBundle;
+use bevy_trauma_shake::TraumaEvent;
+
+use crate::{
+    entity_gc::EntityLifetime,
+    hand::{Hand, Outcome},
+    hand_rules::HandRules,
+    movement::Velocity,
+    particles::HanabiThing,
+    pool::{Dormant, Retire},
+    score::{ComboExtended, ComboStarted},
+    simulation::SimulationSystems,
+    state::{GameState, InGame},
+};
+
+/// Side a hand was thrown for, hands only clash with hands of the other side
+#[derive(Component, Reflect, Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Team {
+    Player,
+    Enemy,
+}
+
+impl Team {
+    #[must_use]
+    pub const fn opponent(self) -> Self {
+        match self {
+            Self::Player => Self::Enemy,
+            Self::Enemy => Self::Player,
+        }
+    }
+}
+
+/// What happens when two identical hands meet
+#[derive(Resource, Reflect, Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub enum TieRule {
+    /// both hands survive and bounce off each other
+    #[default]
+    Bounce,
+    /// both hands are destroyed
+    Annihilate,
+}
+
+/// Sent every time two hands of opposite teams meet
+#[derive(Event, Debug, Clone, Copy)]
+pub struct HandClash {
+    pub first: Entity,
+    pub second: Entity,
+    /// team of `first`, `second` is on the other one
+    pub first_team: Team,
+    /// outcome from the point of view of `first`
+    pub outcome: Outcome,
+    /// where the two hands met
+    pub position: Vec3,
+}
+
+impl HandClash {
+    #[must_use]
+    pub const fn winner(&self) -> Option<Entity> {
+        match self.outcome {
+            Outcome::Win => Some(self.first),
+            Outcome::Lose => Some(self.second),
+            Outcome::Tie => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn winning_team(&self) -> Option<Team> {
+        match self.outcome {
+            Outcome::Win => Some(self.first_team),
+            Outcome::Lose => Some(self.first_team.opponent()),
+            Outcome::Tie => None,
+        }
+    }
+}
+
+/// hand spritesheets are 32x32 pixels, scaled up by their transform
+pub const HAND_RADIUS: f32 = 16.0;
+
+type ClashingHand<'a> = (
+    Entity,
+    &'a Hand,
+    &'a Team,
+    &'a Transform,
+    Option<&'a mut Velocity>,
+);
+
+fn resolve_clashes(
+    mut query: Query<ClashingHand, Without<Dormant>>,
+    tie_rule: Res<TieRule>,
+    rules: Res<HandRules>,
+    mut clashes: EventWriter<HandClash>,
+    mut commands: Commands,
+) {
+    // a hand can overlap several others on the same frame, only the first clash counts
+    let mut destroyed = HashSet::new();
+    let mut combinations = query.iter_combinations_mut();
+    while let Some([first, second]) = combinations.fetch_next() {
+        let (first_entity, first_hand, first_team, first_transform, first_velocity) = first;
+        let (second_entity, second_hand, second_team, second_transform, second_velocity) = second;
+
+        if first_team == second_team
+            || destroyed.contains(&first_entity)
+            || destroyed.contains(&second_entity)
+        {
+            continue;
+        }
+
+        let offset =
+            second_transform.translation.truncate() - first_transform.translation.truncate();
+        let reach = HAND_RADIUS * (first_transform.scale.x + second_transform.scale.x);
+        if offset.length_squared() > reach * reach {
+            continue;
+        }
+
+        let outcome = rules.versus(*first_hand, *second_hand);
+        match outcome {
+            Outcome::Win => {
+                destroyed.insert(second_entity);
+            }
+            Outcome::Lose => {
+                destroyed.insert(first_entity);
+            }
+            Outcome::Tie => match *tie_rule {
+                TieRule::Annihilate => {
+                    destroyed.insert(first_entity);
+                    destroyed.insert(second_entity);
+                }
+                TieRule::Bounce => {
+                    let (Some(mut first_velocity), Some(mut second_velocity)) =
+                        (first_velocity, second_velocity)
+                    else {
+                        continue;
+                    };
+                    let normal = offset.normalize_or_zero().extend(0.0);
+                    let closing_speed = (**first_velocity - **second_velocity).dot(normal);
+                    // already moving apart, the hands are still overlapping from the last bounce
+                    if closing_speed <= 0.0 {
+                        continue;
+                    }
+                    **first_velocity -= normal * closing_speed;
+                    **second_velocity += normal * closing_speed;
+                }
+            },
+        }
+
+        clashes.send(HandClash {
+            first: first_entity,
+            second: second_entity,
+            first_team: *first_team,
+            outcome,
+            position: (first_transform.translation + second_transform.translation) / 2.0,
+        });
+    }
+
+    for entity in destroyed {
+        commands.add(Retire(entity));
+    }
+}
+
+const CLASH_TRAUMA: f32 = 0.1;
+/// growth of the clash particles for every win of the combo of the winning player
+const COMBO_BURST_STEP: f32 = 0.2;
+const MAX_COMBO_BURST: f32 = 3.0;
+
+/// Clashes with a winner shake the screen, and burst bigger the longer the combo they are part of
+fn clash_feedback(
+    mut clashes: EventReader<HandClash>,
+    mut started: EventReader<ComboStarted>,
+    mut extended: EventReader<ComboExtended>,
+    hana: Option<Res<HanabiThing>>,
+    mut trauma: EventWriter<TraumaEvent>,
+    mut commands: Commands,
+) {
+    // winning hand and combo length of the clashes that made a combo
+    let combos: Vec<(Entity, u32)> = started
+        .read()
+        .map(|event| (event.hand, event.combo))
+        .chain(extended.read().map(|event| (event.hand, event.combo)))
+        .collect();
+    for clash in clashes.read() {
+        let Some(winner) = clash.winner() else {
+            continue;
+        };
+        trauma.send(CLASH_TRAUMA.into());
+        // no particles when running headless
+        let Some(hana) = &hana else {
+            continue;
+        };
+        let combo = combos
+            .iter()
+            .find(|(hand, _)| *hand == winner)
+            .map_or(0, |(_, combo)| *combo);
+        let burst = COMBO_BURST_STEP
+            .mul_add(combo as f32, 1.0)
+            .min(MAX_COMBO_BURST);
+        commands.spawn((
+            ParticleEffectBundle {
+                effect: hana.effect().with_z_layer_2d(Some(0.1)),
+                transform: Transform::from_translation(clash.position)
+                    .with_scale(Vec3::splat(burst)),
+                ..Default::default()
+            },
+            EntityLifetime::new(1.0),
+            StateScoped(InGame),
+        ));
+    }
+}
+
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Team>()
+            .register_type::<TieRule>()
+            .init_resource::<TieRule>()
+            .add_event::<HandClash>()
+            .add_systems(
+                FixedUpdate,
+                resolve_clashes.in_set(SimulationSystems::Collide),
+            )
+            .add_systems(Update, clash_feedback.run_if(in_state(GameState::Playing)));
+    }
+}
@@ -0,0 +1,204 @@
+use std::path::PathBuf;
+
+use bevy::{log::warn, prelude::Resource};
+
+/// Options given on the command line, e.g. `RPSGame --seed 42 --record run.replay.ron`
+///
+/// Every option can also be given through an `RPS_*` environment variable, the command line wins.
+/// Must be inserted before the plugins reading it are added.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct LaunchOptions {
+    /// seed of the [`GameRng`](crate::rng::GameRng), random if not given
+    pub seed: Option<u64>,
+    /// file the player actions are recorded to
+    pub record: Option<PathBuf>,
+    /// file to play actions back from, instead of the live keyboard and gamepads
+    pub replay: Option<PathBuf>,
+    /// file the input bindings are read from and saved to
+    pub bindings: Option<PathBuf>,
+    /// asset path of the hand ruleset, e.g. `rules/rpsls.hands.ron`
+    pub hand_rules: Option<String>,
+    /// simulation ticks per second
+    pub tick_rate: Option<f64>,
+    /// run without a window, a GPU or audio, see [`HeadlessPlugin`](crate::headless::HeadlessPlugin)
+    pub headless: bool,
+    /// start playing right away, e.g. for headless runs without a replay to get past the menu
+    pub skip_menu: bool,
+}
+
+/// flags that do not need a value, `--flag` is the same as `--flag=true`
+const SWITCHES: [&str; 2] = ["--headless", "--skip-menu"];
+
+impl LaunchOptions {
+    #[must_use]
+    pub fn from_env() -> Self {
+        Self::from_sources(|var| std::env::var(var).ok(), std::env::args().skip(1))
+    }
+
+    /// `read_var` reads an environment variable, `args` do not include the program name
+    fn from_sources(
+        read_var: impl Fn(&str) -> Option<String>,
+        args: impl IntoIterator<Item = String>,
+    ) -> Self {
+        let mut options = Self::default();
+        for (flag, var) in [
+            ("--seed", "RPS_SEED"),
+            ("--record", "RPS_RECORD"),
+            ("--replay", "RPS_REPLAY"),
+            ("--bindings", "RPS_BINDINGS"),
+            ("--hand-rules", "RPS_HAND_RULES"),
+            ("--tick-rate", "RPS_TICK_RATE"),
+            ("--headless", "RPS_HEADLESS"),
+            ("--skip-menu", "RPS_SKIP_MENU"),
+        ] {
+            if let Some(value) = read_var(var) {
+                options.set(flag, &value);
+            }
+        }
+        options.parse_args(args);
+        options
+    }
+
+    fn parse_args(&mut self, args: impl IntoIterator<Item = String>) {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // both `--flag value` and `--flag=value` are accepted
+            let (flag, value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            if SWITCHES.contains(&flag.as_str()) && value.is_none() {
+                self.set(&flag, "true");
+                continue;
+            }
+            let Some(value) = value.or_else(|| args.next()) else {
+                warn!("missing value for {flag}");
+                continue;
+            };
+            self.set(&flag, &value);
+        }
+    }
+
+    fn set(&mut self, flag: &str, value: &str) {
+        match flag {
+            "--seed" => self.seed = parse_flag(flag, value).or(self.seed),
+            "--record" => self.record = Some(value.into()),
+            "--replay" => self.replay = Some(value.into()),
+            "--bindings" => self.bindings = Some(value.into()),
+            "--hand-rules" => self.hand_rules = Some(value.into()),
+            "--headless" => self.headless = parse_flag(flag, value).unwrap_or(self.headless),
+            "--skip-menu" => self.skip_menu = parse_flag(flag, value).unwrap_or(self.skip_menu),
+            "--tick-rate" => {
+                self.tick_rate = parse_flag::<f64>(flag, value)
+                    .filter(|rate| *rate > 0.0)
+                    .or(self.tick_rate);
+            }
+            _ => warn!("ignoring unknown argument {flag}"),
+        }
+    }
+}
+
+fn parse_flag<T: std::str::FromStr>(flag: &str, value: &str) -> Option<T>
+where
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|error| warn!("invalid value {value:?} for {flag}: {error}"))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// options from `vars` as `(name, value)` pairs, and the command line `args`
+    fn options(vars: &[(&str, &str)], args: &str) -> LaunchOptions {
+        LaunchOptions::from_sources(
+            |var| {
+                vars.iter()
+                    .find(|(name, _)| *name == var)
+                    .map(|(_, value)| (*value).to_string())
+            },
+            args.split_whitespace().map(String::from),
+        )
+    }
+
+    #[test]
+    fn reads_every_flag() {
+        let options = options(
+            &[],
+            "--seed 42 --record run.ron --replay old.ron --bindings keys.ron \
+             --hand-rules rules/rpsls.hands.ron --tick-rate 30 --headless --skip-menu",
+        );
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(options.record, Some("run.ron".into()));
+        assert_eq!(options.replay, Some("old.ron".into()));
+        assert_eq!(options.bindings, Some("keys.ron".into()));
+        assert_eq!(options.hand_rules.as_deref(), Some("rules/rpsls.hands.ron"));
+        assert_eq!(options.tick_rate, Some(30.0));
+        assert!(options.headless);
+        assert!(options.skip_menu);
+    }
+
+    #[test]
+    fn accepts_values_after_an_equal_sign() {
+        let options = options(&[], "--seed=7 --headless=true --skip-menu=false");
+        assert_eq!(options.seed, Some(7));
+        assert!(options.headless);
+        assert!(!options.skip_menu);
+    }
+
+    #[test]
+    fn reads_environment_variables() {
+        let options = options(&[("RPS_SEED", "3"), ("RPS_HEADLESS", "true")], "");
+        assert_eq!(options.seed, Some(3));
+        assert!(options.headless);
+    }
+
+    #[test]
+    fn command_line_wins_over_the_environment() {
+        let options = options(
+            &[("RPS_SEED", "3"), ("RPS_SKIP_MENU", "true")],
+            "--seed 4 --skip-menu=false",
+        );
+        assert_eq!(options.seed, Some(4));
+        assert!(!options.skip_menu);
+    }
+
+    #[test]
+    fn invalid_values_keep_the_previous_one() {
+        let vars = [
+            ("RPS_SEED", "3"),
+            ("RPS_TICK_RATE", "30"),
+            ("RPS_HEADLESS", "true"),
+        ];
+        for args in [
+            "--seed -1",
+            "--seed many",
+            "--tick-rate 0",
+            "--tick-rate -60",
+            "--tick-rate NaN",
+            "--headless=maybe",
+        ] {
+            let options = options(&vars, args);
+            assert_eq!(options.seed, Some(3), "{args}");
+            assert_eq!(options.tick_rate, Some(30.0), "{args}");
+            assert!(options.headless, "{args}");
+        }
+    }
+
+    #[test]
+    fn invalid_environment_variables_are_ignored() {
+        let options = options(&[("RPS_SEED", "none"), ("RPS_TICK_RATE", "fast")], "");
+        assert_eq!(options.seed, None);
+        assert_eq!(options.tick_rate, None);
+    }
+
+    #[test]
+    fn skips_unknown_flags_and_missing_values() {
+        let options = options(&[], "--speed 2 --seed 5 --record");
+        assert_eq!(options.seed, Some(5));
+        assert_eq!(options.record, None);
+    }
+}
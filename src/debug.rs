@@ -1,7 +1,7 @@
 use bevy::{
     diagnostic::{
-        Diagnostic, DiagnosticsStore, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin,
-        LogDiagnosticsPlugin,
+        Diagnostic, DiagnosticPath, DiagnosticsStore, EntityCountDiagnosticsPlugin,
+        FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin,
     },
     input::common_conditions::input_toggle_active,
     prelude::*,
@@ -9,6 +9,11 @@ use bevy::{
 #[cfg(feature = "debug")]
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
+use crate::{
+    actions::Action,
+    pool::{POOL_DORMANT, POOL_SIZE},
+};
+
 #[derive(Component)]
 struct FpsRoot;
 
@@ -124,12 +129,12 @@ fn fps_text_update_system(
     }
 }
 
-/// Toggle the FPS counter when pressing F12
+/// Toggle the FPS counter on `Action::ToggleDebug`
 fn fps_counter_showhide(
     mut q: Query<&mut Visibility, With<FpsRoot>>,
-    kbd: Res<ButtonInput<KeyCode>>,
+    actions: Res<ButtonInput<Action>>,
 ) {
-    if kbd.just_pressed(KeyCode::F12) {
+    if actions.just_pressed(Action::ToggleDebug) {
         let mut vis = q.single_mut();
         *vis = match *vis {
             Visibility::Hidden => Visibility::Visible,
@@ -241,12 +246,83 @@ fn entity_count_text_update_system(
     }
 }
 
-/// Toggle the FPS counter when pressing F12
+/// Toggle the FPS counter on `Action::ToggleDebug`
 fn entity_counter_showhide(
     mut q: Query<&mut Visibility, With<EntityCountRoot>>,
-    kbd: Res<ButtonInput<KeyCode>>,
+    actions: Res<ButtonInput<Action>>,
+) {
+    if actions.just_pressed(Action::ToggleDebug) {
+        let mut vis = q.single_mut();
+        *vis = match *vis {
+            Visibility::Hidden => Visibility::Visible,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+#[derive(Component)]
+struct PoolRoot;
+
+/// Marker to find the text entity so we can update it
+#[derive(Component)]
+struct PoolText;
+
+fn setup_pool_counter(mut commands: Commands) {
+    let style = TextStyle {
+        font_size: 16.0,
+        color: Color::WHITE,
+        ..default()
+    };
+    commands
+        .spawn((
+            PoolRoot,
+            Name::new("PoolRoot"),
+            NodeBundle {
+                background_color: BackgroundColor(Color::BLACK.with_alpha(0.5)),
+                z_index: ZIndex::Global(i32::MAX),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    // right below the entity counter
+                    right: Val::Percent(1.),
+                    top: Val::Percent(5.),
+                    padding: UiRect::all(Val::Px(4.0)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                PoolText,
+                Name::new("PoolText"),
+                TextBundle::from_sections([
+                    TextSection::new("Pool: ", style.clone()),
+                    TextSection::new(" N/A", style),
+                ]),
+            ));
+        });
+}
+
+/// dormant hands over the total number of hands in the pool
+fn pool_text_update_system(
+    diagnostics: Res<DiagnosticsStore>,
+    mut query: Query<&mut Text, With<PoolText>>,
+) {
+    let value = |path: &DiagnosticPath| diagnostics.get(path).and_then(Diagnostic::value);
+    for mut text in &mut query {
+        text.sections[1].value = match (value(&POOL_DORMANT), value(&POOL_SIZE)) {
+            (Some(dormant), Some(size)) => format!("{dormant:>4.0}/{size:<4.0}"),
+            _ => " N/A".into(),
+        };
+    }
+}
+
+/// Toggle the pool counter on `Action::ToggleDebug`
+fn pool_counter_showhide(
+    mut q: Query<&mut Visibility, With<PoolRoot>>,
+    actions: Res<ButtonInput<Action>>,
 ) {
-    if kbd.just_pressed(KeyCode::F12) {
+    if actions.just_pressed(Action::ToggleDebug) {
         let mut vis = q.single_mut();
         *vis = match *vis {
             Visibility::Hidden => Visibility::Visible,
@@ -260,7 +336,7 @@ pub struct DebugPlugin;
 impl Plugin for DebugPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((
-            WorldInspectorPlugin::new().run_if(input_toggle_active(false, KeyCode::Equal)),
+            WorldInspectorPlugin::new().run_if(input_toggle_active(false, Action::ToggleInspector)),
             bevy_framepace::debug::DiagnosticsPlugin,
             FrameTimeDiagnosticsPlugin,
             LogDiagnosticsPlugin::default(),
@@ -272,6 +348,8 @@ impl Plugin for DebugPlugin {
         .add_systems(
             Update,
             (entity_count_text_update_system, entity_counter_showhide),
-        );
+        )
+        .add_systems(Startup, setup_pool_counter)
+        .add_systems(Update, (pool_text_update_system, pool_counter_showhide));
     }
 }
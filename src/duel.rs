@@ -0,0 +1,454 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    color::Color,
+    core::Name,
+    input::ButtonInput,
+    prelude::{
+        default, in_state, not, AppExtStates, BuildChildren, ChildBuilder, Commands, Component,
+        Entity, EventWriter, ImageBundle, IntoSystemConfigs, NextState, NodeBundle, OnEnter,
+        OnExit, Query, Res, ResMut, Resource, StateScoped, States, Text, TextBundle, Visibility,
+    },
+    sprite::TextureAtlas,
+    text::TextStyle,
+    time::{Time, Timer, TimerMode},
+    ui::{AlignItems, FlexDirection, JustifyContent, Style, UiImage, Val},
+};
+
+use crate::{
+    actions::{Action, Bindings, PlayerActions},
+    animations::AnimationTimer,
+    hand::{Hand, HandAnimations, Outcome},
+    hand_rules::HandRules,
+    player::{PlayerIndex, MAX_PLAYERS},
+    rng::{GameRng, RngStream},
+    rounds::{Match, RoundEnded},
+    state::{has_cannons, GameMode, GameState, InGame},
+};
+
+/// seconds both players have to pick their hand
+const CHOOSE_TIME: f32 = 3.0;
+/// seconds the picked hands are shown before the round ends
+const REVEAL_TIME: f32 = 1.5;
+/// seconds between two frames of the revealed hands
+const REVEAL_FRAME_TIME: f32 = 0.15;
+const ICON_SIZE: f32 = 160.0;
+
+/// Actions picking the first hands, in rules order, the others are reached by cycling
+const PICK_ACTIONS: [(Action, &str); 4] = [
+    (Action::MoveLeft, "left"),
+    (Action::MoveUp, "up"),
+    (Action::MoveRight, "right"),
+    (Action::MoveDown, "down"),
+];
+
+/// Step of a duel round, the duel is `Inactive` outside of a duel run
+#[derive(States, Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DuelPhase {
+    #[default]
+    Inactive,
+    /// both players pick a hand in secret before the countdown is over
+    Choosing,
+    /// both hands are shown and the round is decided
+    Revealing,
+}
+
+/// Hands picked in the current round and time left in its phase
+#[derive(Resource, Debug)]
+struct Duel {
+    picks: [Option<Hand>; MAX_PLAYERS],
+    /// hand each player cycled to, picked when they fire
+    cycled: [Hand; MAX_PLAYERS],
+    timer: Timer,
+}
+
+impl Default for Duel {
+    fn default() -> Self {
+        Self {
+            picks: [None; MAX_PLAYERS],
+            cycled: [Hand::new(0); MAX_PLAYERS],
+            timer: Timer::from_seconds(CHOOSE_TIME, TimerMode::Once),
+        }
+    }
+}
+
+impl Duel {
+    /// a player who did not pick in time loses the round
+    fn winner(&self, rules: &HandRules) -> Option<PlayerIndex> {
+        match self.picks {
+            [Some(first), Some(second)] => match rules.versus(first, second) {
+                Outcome::Win => Some(PlayerIndex(0)),
+                Outcome::Lose => Some(PlayerIndex(1)),
+                Outcome::Tie => None,
+            },
+            [Some(_), None] => Some(PlayerIndex(0)),
+            [None, Some(_)] => Some(PlayerIndex(1)),
+            [None, None] => None,
+        }
+    }
+}
+
+/// Hand shown for a player once revealed
+#[derive(Component, Debug)]
+struct DuelIcon(PlayerIndex);
+
+/// Text of the duel screen that changes during a round
+#[derive(Component, Debug)]
+enum DuelText {
+    Countdown,
+    /// whether a player picked yet, then their hand
+    Status(PlayerIndex),
+}
+
+fn text_style(font_size: f32) -> TextStyle {
+    TextStyle {
+        font_size,
+        color: Color::WHITE,
+        ..default()
+    }
+}
+
+/// hand picked by a player this frame, if any, `cycled` moves to the next hand on `CycleHand`
+fn picked(input: &ButtonInput<Action>, rules: &HandRules, cycled: &mut Hand) -> Option<Hand> {
+    if input.just_pressed(Action::CycleHand) {
+        *cycled = rules.cycle(*cycled);
+    }
+    (0..rules.count())
+        .find(|rank| {
+            input.just_pressed(Action::SelectHand(*rank))
+                || PICK_ACTIONS
+                    .get(*rank)
+                    .is_some_and(|(action, _)| input.just_pressed(*action))
+        })
+        .map(Hand::new)
+        .or_else(|| input.just_pressed(Action::Fire).then_some(*cycled))
+}
+
+/// how to pick the first hands, e.g. "left Rock, up Paper, right Scissors"
+fn pick_hint(rules: &HandRules) -> String {
+    PICK_ACTIONS
+        .iter()
+        .take(rules.count())
+        .enumerate()
+        .map(|(rank, (_, name))| format!("{name} {}", rules.name(Hand::new(rank))))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// buttons of a player reaching every hand, e.g. "`KeyA` cycles, `Space` picks"
+fn cycle_hint(bindings: &Bindings, player: PlayerIndex, rules: &HandRules) -> String {
+    let hint = format!(
+        "{} cycles, {} picks",
+        bindings.describe(player, Action::CycleHand),
+        bindings.describe(player, Action::Fire)
+    );
+    // rank keys are only worth listing when every hand has one
+    let ranks: Vec<_> = (0..rules.count())
+        .map(|rank| bindings.get(player, Action::SelectHand(rank)))
+        .collect();
+    match (ranks.first(), ranks.last()) {
+        (Some([first, ..]), Some([last, ..])) if ranks.iter().all(|keys| !keys.is_empty()) => {
+            format!("{hint}, {first}-{last} by rank")
+        }
+        _ => hint,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_round(
+    mode: Res<GameMode>,
+    current: Res<Match>,
+    rules: Res<HandRules>,
+    bindings: Res<Bindings>,
+    hand_animations: Res<HandAnimations>,
+    mut rng: ResMut<GameRng>,
+    mut duel: ResMut<Duel>,
+    mut next_phase: ResMut<NextState<DuelPhase>>,
+    mut commands: Commands,
+) {
+    *duel = Duel::default();
+    if mode.against_cpu() {
+        duel.picks[1] = Some(rules.random(rng.stream(RngStream::Duel)));
+    }
+    next_phase.set(DuelPhase::Choosing);
+
+    commands
+        .spawn((
+            Name::new("Duel"),
+            StateScoped(InGame),
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::SpaceEvenly,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("{}  best of {}", current.tally(*mode), current.best_of),
+                text_style(32.0),
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(80.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for player in (0..MAX_PLAYERS).map(PlayerIndex) {
+                        if player.0 == 1 {
+                            parent.spawn((
+                                DuelText::Countdown,
+                                TextBundle::from_section("", text_style(96.0)),
+                            ));
+                        }
+                        let hint = (player.0 < mode.players())
+                            .then(|| cycle_hint(&bindings, player, &rules));
+                        spawn_side(
+                            parent,
+                            &hand_animations,
+                            player,
+                            &mode.player_name(player),
+                            hint,
+                        );
+                    }
+                });
+            parent.spawn(TextBundle::from_section(
+                format!("pick with {}", pick_hint(&rules)),
+                text_style(24.0),
+            ));
+        });
+}
+
+/// name, hidden hand, status and buttons of a player, side by side with the other one
+fn spawn_side(
+    parent: &mut ChildBuilder,
+    hand_animations: &HandAnimations,
+    player: PlayerIndex,
+    name: &str,
+    hint: Option<String>,
+) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                name,
+                TextStyle {
+                    color: player.color(),
+                    ..text_style(40.0)
+                },
+            ));
+            parent.spawn((
+                DuelIcon(player),
+                ImageBundle {
+                    style: Style {
+                        width: Val::Px(ICON_SIZE),
+                        height: Val::Px(ICON_SIZE),
+                        ..default()
+                    },
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                TextureAtlas {
+                    layout: hand_animations.layout(),
+                    index: hand_animations.indices().first,
+                },
+                hand_animations.indices(),
+            ));
+            parent.spawn((
+                DuelText::Status(player),
+                TextBundle::from_section("", text_style(28.0)),
+            ));
+            if let Some(hint) = hint {
+                parent.spawn(TextBundle::from_section(hint, text_style(18.0)));
+            }
+        });
+}
+
+/// Human players lock in the first hand they pick, the computer picked when the round started
+fn choose_hands(
+    time: Res<Time>,
+    mode: Res<GameMode>,
+    players: Res<PlayerActions>,
+    rules: Res<HandRules>,
+    mut duel: ResMut<Duel>,
+    mut next_phase: ResMut<NextState<DuelPhase>>,
+    mut texts: Query<(&DuelText, &mut Text)>,
+) {
+    let duel = &mut *duel;
+    for player in (0..mode.players()).map(PlayerIndex) {
+        if duel.picks[player.0].is_none() {
+            duel.picks[player.0] = picked(players.get(player), &rules, &mut duel.cycled[player.0]);
+        }
+    }
+    duel.timer.tick(time.delta());
+
+    for (text, mut label) in &mut texts {
+        label.sections[0].value = match text {
+            DuelText::Countdown => format!("{:.0}", duel.timer.remaining_secs().ceil()),
+            DuelText::Status(player) if duel.picks[player.0].is_some() => "ready".to_string(),
+            DuelText::Status(player) => {
+                format!("choosing... {}", rules.name(duel.cycled[player.0]))
+            }
+        };
+    }
+
+    if duel.timer.finished() || duel.picks.iter().all(Option::is_some) {
+        duel.timer = Timer::from_seconds(REVEAL_TIME, TimerMode::Once);
+        next_phase.set(DuelPhase::Revealing);
+    }
+}
+
+fn reveal_hands(
+    duel: Res<Duel>,
+    rules: Res<HandRules>,
+    hand_animations: Res<HandAnimations>,
+    mut icons: Query<(Entity, &DuelIcon, &mut UiImage, &mut Visibility)>,
+    mut texts: Query<(&DuelText, &mut Text)>,
+    mut commands: Commands,
+) {
+    for (entity, DuelIcon(player), mut image, mut visibility) in &mut icons {
+        let Some(hand) = duel.picks[player.0] else {
+            continue;
+        };
+        image.texture = hand_animations.get(hand);
+        *visibility = Visibility::Inherited;
+        // play the hand animation while it is shown
+        commands
+            .entity(entity)
+            .insert(AnimationTimer::repeating(REVEAL_FRAME_TIME));
+    }
+    for (text, mut label) in &mut texts {
+        label.sections[0].value = match text {
+            DuelText::Countdown => "vs".to_string(),
+            DuelText::Status(player) => duel.picks[player.0].map_or_else(
+                || "too slow".to_string(),
+                |hand| rules.name(hand).to_string(),
+            ),
+        };
+    }
+}
+
+fn finish_reveal(
+    time: Res<Time>,
+    rules: Res<HandRules>,
+    mut duel: ResMut<Duel>,
+    mut ended: EventWriter<RoundEnded>,
+) {
+    if duel.timer.tick(time.delta()).just_finished() {
+        ended.send(RoundEnded {
+            winner: duel.winner(&rules),
+        });
+    }
+}
+
+fn leave_duel(mut next_phase: ResMut<NextState<DuelPhase>>) {
+    next_phase.set(DuelPhase::Inactive);
+}
+
+pub struct DuelPlugin;
+
+impl Plugin for DuelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<DuelPhase>()
+            .init_resource::<Duel>()
+            .add_systems(OnEnter(InGame), start_round.run_if(not(has_cannons)))
+            .add_systems(OnExit(InGame), leave_duel)
+            .add_systems(OnEnter(DuelPhase::Revealing), reveal_hands)
+            .add_systems(
+                Update,
+                (
+                    choose_hands.run_if(in_state(DuelPhase::Choosing)),
+                    finish_reveal.run_if(in_state(DuelPhase::Revealing)),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn winner(picks: [Option<&str>; MAX_PLAYERS]) -> Option<PlayerIndex> {
+        let rules = HandRules::default();
+        let duel = Duel {
+            picks: picks.map(|pick| pick.map(|name| rules.find(name).unwrap())),
+            ..Duel::default()
+        };
+        duel.winner(&rules)
+    }
+
+    #[test]
+    fn stronger_hand_wins() {
+        assert_eq!(
+            winner([Some("Rock"), Some("Scissors")]),
+            Some(PlayerIndex(0))
+        );
+        assert_eq!(winner([Some("Rock"), Some("Paper")]), Some(PlayerIndex(1)));
+    }
+
+    #[test]
+    fn same_hands_tie() {
+        assert_eq!(winner([Some("Paper"), Some("Paper")]), None);
+    }
+
+    #[test]
+    fn missed_pick_loses() {
+        assert_eq!(winner([Some("Scissors"), None]), Some(PlayerIndex(0)));
+        assert_eq!(winner([None, Some("Rock")]), Some(PlayerIndex(1)));
+    }
+
+    #[test]
+    fn no_picks_tie() {
+        assert_eq!(winner([None, None]), None);
+    }
+
+    #[test]
+    fn fire_picks_the_cycled_hand() {
+        let rules = HandRules::default();
+        let mut cycled = Hand::new(0);
+        let mut input = ButtonInput::default();
+        input.press(Action::CycleHand);
+        assert_eq!(picked(&input, &rules, &mut cycled), None);
+        assert_eq!(cycled, Hand::new(1));
+
+        input.clear();
+        input.press(Action::Fire);
+        assert_eq!(picked(&input, &rules, &mut cycled), Some(Hand::new(1)));
+    }
+
+    #[test]
+    fn directions_and_ranks_pick_directly() {
+        let rules = HandRules::default();
+        let mut cycled = Hand::new(0);
+        for (action, rank) in [
+            (Action::MoveUp, 1),
+            (Action::MoveRight, 2),
+            (Action::SelectHand(2), 2),
+        ] {
+            let mut input = ButtonInput::default();
+            input.press(action);
+            assert_eq!(picked(&input, &rules, &mut cycled), Some(Hand::new(rank)));
+        }
+        // out of the ruleset
+        let mut input = ButtonInput::default();
+        input.press(Action::MoveDown);
+        assert_eq!(picked(&input, &rules, &mut cycled), None);
+    }
+}
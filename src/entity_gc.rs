@@ -1,35 +1,134 @@
 use bevy::{
-    app::{Plugin, Update},
-    prelude::{Commands, Component, Deref, DerefMut, Entity, Query, Res},
+    app::{FixedUpdate, Plugin},
+    math::{Rect, Vec3, Vec3Swizzles},
+    prelude::{
+        Camera2d, Commands, Component, Deref, DerefMut, Entity, Event, EventWriter,
+        GlobalTransform, IntoSystemConfigs, OrthographicProjection, Query, Res, Transform, With,
+        Without,
+    },
     reflect::Reflect,
     time::{Time, Timer, TimerMode},
 };
 
+use crate::{
+    arena::Arena,
+    movement::Velocity,
+    pool::{Dormant, Retire},
+    simulation::SimulationSystems,
+};
+
 #[derive(Component, Reflect, Deref, DerefMut)]
 pub struct EntityLifetime(Timer);
 impl EntityLifetime {
+    #[must_use]
     pub fn new(ttl: f32) -> Self {
         Self(Timer::from_seconds(ttl, TimerMode::Once))
     }
 }
 
 fn delete_expired_entities(
-    mut query: Query<(Entity, &mut EntityLifetime)>,
+    mut query: Query<(Entity, &mut EntityLifetime), Without<Dormant>>,
     time: Res<Time>,
     mut commands: Commands,
 ) {
     query.iter_mut().for_each(|(entity, mut lifetimer)| {
         lifetimer.tick(time.delta());
         if lifetimer.just_finished() {
-            commands.entity(entity).despawn();
+            commands.add(Retire(entity));
         }
     });
 }
 
+/// Area an entity with [`DespawnOutsideBounds`] has to stay in
+#[derive(Reflect, Debug, Clone, Copy, Default)]
+pub enum Bounds {
+    /// the playable [`Arena`]
+    #[default]
+    Arena,
+    /// what the camera currently shows
+    Viewport,
+}
+
+/// Despawns the entity once it is further than `margin` outside of its `bounds`
+///
+/// Entities with a [`Velocity`] heading back inside are kept, so things can be spawned off-screen.
+#[derive(Component, Reflect, Debug, Clone, Copy, Default)]
+pub struct DespawnOutsideBounds {
+    pub bounds: Bounds,
+    pub margin: f32,
+}
+
+impl DespawnOutsideBounds {
+    #[must_use]
+    pub const fn arena(margin: f32) -> Self {
+        Self {
+            bounds: Bounds::Arena,
+            margin,
+        }
+    }
+
+    #[must_use]
+    pub const fn viewport(margin: f32) -> Self {
+        Self {
+            bounds: Bounds::Viewport,
+            margin,
+        }
+    }
+}
+
+/// Sent when an entity is removed from play for leaving its [`Bounds`]
+#[derive(Event, Debug)]
+pub struct LeftBounds {
+    pub entity: Entity,
+    pub position: Vec3,
+}
+
+fn delete_out_of_bounds_entities(
+    query: Query<(Entity, &Transform, &DespawnOutsideBounds, Option<&Velocity>), Without<Dormant>>,
+    cameras: Query<(&GlobalTransform, &OrthographicProjection), With<Camera2d>>,
+    arena: Res<Arena>,
+    mut left: EventWriter<LeftBounds>,
+    mut commands: Commands,
+) {
+    let viewport = cameras
+        .get_single()
+        .map_or(arena.rect, |(transform, projection)| {
+            let center = transform.translation().xy();
+            Rect::from_corners(projection.area.min + center, projection.area.max + center)
+        });
+    for (entity, transform, despawn, velocity) in &query {
+        let bounds = match despawn.bounds {
+            Bounds::Arena => arena.rect,
+            Bounds::Viewport => viewport,
+        }
+        .inflate(despawn.margin);
+        let position = transform.translation.xy();
+        if bounds.contains(position) {
+            continue;
+        }
+        let inward = position.clamp(bounds.min, bounds.max) - position;
+        if velocity.is_some_and(|velocity| velocity.xy().dot(inward) > 0.0) {
+            continue;
+        }
+        left.send(LeftBounds {
+            entity,
+            position: transform.translation,
+        });
+        commands.add(Retire(entity));
+    }
+}
+
 pub struct EntityGcPlugin;
 impl Plugin for EntityGcPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.register_type::<EntityLifetime>()
-            .add_systems(Update, delete_expired_entities);
+            .register_type::<DespawnOutsideBounds>()
+            .add_event::<LeftBounds>()
+            .add_systems(
+                FixedUpdate,
+                (delete_expired_entities, delete_out_of_bounds_entities)
+                    .chain()
+                    .in_set(SimulationSystems::Despawn),
+            );
     }
 }
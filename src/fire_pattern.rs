@@ -0,0 +1,113 @@
+// the `Reflect` derive of `FirePattern` binds the variant fields to underscored names
+#![allow(clippy::used_underscore_binding)]
+
+use std::f32::consts::TAU;
+
+use bevy::{prelude::Component, reflect::Reflect};
+use rand::Rng;
+use serde::Deserialize;
+
+/// How the hands of a single trigger pull are laid out
+///
+/// Angles are in degrees, relative to the direction the shooter is facing.
+#[derive(Component, Reflect, Deserialize, Debug, Clone, Default)]
+pub enum FirePattern {
+    /// one hand, straight ahead
+    #[default]
+    Single,
+    /// `count` hands evenly spread over `angle`
+    Fan { count: u32, angle: f32 },
+    /// `count` hands one after the other, `interval` seconds apart
+    Burst { count: u32, interval: f32 },
+    /// `arms` hands evenly spread all around, rotating by `step` every pull
+    Spiral { arms: u32, step: f32 },
+    /// `count` hands at random in a cone of `angle`, with speeds varying by up to `speed_jitter`
+    Shotgun {
+        count: u32,
+        angle: f32,
+        speed_jitter: f32,
+    },
+}
+
+/// Bookkeeping of patterns that change from one pull to the next
+#[derive(Component, Reflect, Debug, Default)]
+pub struct FirePatternState {
+    /// current rotation of spirals, in radians
+    rotation: f32,
+}
+
+/// One hand of a pattern
+#[derive(Reflect, Debug, Clone, Copy)]
+pub struct Shot {
+    /// seconds after the trigger pull
+    pub delay: f32,
+    /// radians, relative to the direction the shooter is facing
+    pub angle: f32,
+    /// multiplier of the weapon projectile speed
+    pub speed_factor: f32,
+}
+
+impl Shot {
+    const fn aimed(angle: f32) -> Self {
+        Self {
+            delay: 0.0,
+            angle,
+            speed_factor: 1.0,
+        }
+    }
+}
+
+/// random value within `width` of zero, zero if `width` is negative or not a number
+fn spread(rng: &mut impl Rng, width: f32) -> f32 {
+    // `gen_range` panics on empty or infinite ranges
+    if width > 0.0 && width.is_finite() {
+        rng.gen_range(-width..=width)
+    } else {
+        0.0
+    }
+}
+
+impl FirePattern {
+    pub fn shots(&self, state: &mut FirePatternState, rng: &mut impl Rng) -> Vec<Shot> {
+        match *self {
+            Self::Single => vec![Shot::aimed(0.0)],
+            Self::Fan { count, angle } => {
+                let angle = angle.to_radians();
+                let step = if count > 1 {
+                    angle / (count - 1) as f32
+                } else {
+                    0.0
+                };
+                (0..count)
+                    .map(|i| Shot::aimed(step.mul_add(i as f32, -angle / 2.0)))
+                    .collect()
+            }
+            Self::Burst { count, interval } => (0..count)
+                .map(|i| Shot {
+                    delay: interval * i as f32,
+                    ..Shot::aimed(0.0)
+                })
+                .collect(),
+            Self::Spiral { arms, step } => {
+                let rotation = state.rotation;
+                state.rotation = (state.rotation + step.to_radians()) % TAU;
+                (0..arms)
+                    .map(|i| Shot::aimed((TAU / arms as f32).mul_add(i as f32, rotation)))
+                    .collect()
+            }
+            Self::Shotgun {
+                count,
+                angle,
+                speed_jitter,
+            } => {
+                let half_angle = angle.to_radians() / 2.0;
+                (0..count)
+                    .map(|_| Shot {
+                        speed_factor: 1.0 + spread(rng, speed_jitter),
+                        ..Shot::aimed(spread(rng, half_angle))
+                    })
+                    .collect()
+            }
+        }
+    }
+}
@@ -1,23 +1,13 @@
-use crate::{
-    animations::{AnimatableSpriteBundle, AnimationIndices},
-    particles::HanabiThing,
-};
+use crate::animations::{AnimatableSpriteBundle, AnimationIndices};
 use bevy::{
     app::{App, Plugin, Update},
     asset::{AssetServer, Assets, Handle},
-    ecs::{
-        component::Component,
-        system::{Commands, Resource},
-    },
-    input::ButtonInput,
+    ecs::{component::Component, system::Resource},
     math::UVec2,
-    prelude::{Bundle, Changed, Entity, EventWriter, FromWorld, Image, KeyCode, Query, Res, World},
+    prelude::{Bundle, DetectChanges, FromWorld, Image, Query, Ref, Res, World},
     reflect::Reflect,
     sprite::TextureAtlasLayout,
 };
-use bevy_hanabi::{CompiledParticleEffect, EffectSpawner};
-use bevy_trauma_shake::TraumaEvent;
-use rand::seq::SliceRandom;
 
 pub struct HandPlugin;
 
@@ -26,59 +16,60 @@ impl Plugin for HandPlugin {
         app.register_type::<Hand>()
             .init_resource::<HandAnimations>()
             // .add_systems(Startup, spawn_hand)
-            .add_systems(Update, (change_hand, sync_hand_animation));
+            .add_systems(Update, sync_hand_animation);
     }
 }
 
-#[derive(Component, Reflect, Debug, Eq, Hash, PartialEq, Copy, Clone)]
-pub enum Hand {
-    Rock,
-    Paper,
-    Scissors,
-}
+/// Hand type, as an index in the active [`HandRules`](crate::hand_rules::HandRules)
+#[derive(Component, Reflect, Debug, Default, Eq, Hash, PartialEq, Copy, Clone)]
+pub struct Hand(u8);
 
 impl Hand {
-    pub fn random() -> Self {
-        use Hand::{Paper, Rock, Scissors};
-        *[Rock, Paper, Scissors]
-            .choose(&mut rand::thread_rng())
-            .unwrap()
+    /// # Panics
+    /// when `index` does not fit in a `u8`, rulesets are capped well below that
+    #[must_use]
+    pub fn new(index: usize) -> Self {
+        Self(u8::try_from(index).expect("too many hand types"))
     }
 
-    const fn cycle(self) -> Self {
-        use Hand::{Paper, Rock, Scissors};
-        match self {
-            Rock => Paper,
-            Paper => Scissors,
-            Scissors => Rock,
-        }
+    #[must_use]
+    pub const fn index(self) -> usize {
+        self.0 as usize
     }
 }
 
+#[derive(Reflect, Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Outcome {
+    Win,
+    Lose,
+    Tie,
+}
+
 /// assumes that all hand animations are 4 frames spritesheets of 32/32 pixels
 #[derive(Resource, Reflect)]
 pub struct HandAnimations {
-    rock: Handle<Image>,
-    paper: Handle<Image>,
-    scissors: Handle<Image>,
+    /// spritesheet of each hand type, in [`HandRules`](crate::hand_rules::HandRules) order
+    sprites: Vec<Handle<Image>>,
     atlas_layout: Handle<TextureAtlasLayout>,
     indices: AnimationIndices,
 }
 
 impl HandAnimations {
+    #[must_use]
     pub fn get(&self, hand: Hand) -> Handle<Image> {
-        use Hand::*;
-        match hand {
-            Rock => self.rock.clone(),
-            Paper => self.paper.clone(),
-            Scissors => self.scissors.clone(),
-        }
+        self.sprites.get(hand.index()).cloned().unwrap_or_default()
     }
 
+    pub fn set_sprites(&mut self, sprites: Vec<Handle<Image>>) {
+        self.sprites = sprites;
+    }
+
+    #[must_use]
     pub fn layout(&self) -> Handle<TextureAtlasLayout> {
         self.atlas_layout.clone()
     }
 
+    #[must_use]
     pub fn indices(&self) -> AnimationIndices {
         self.indices.clone()
     }
@@ -88,9 +79,12 @@ impl FromWorld for HandAnimations {
     fn from_world(world: &mut World) -> Self {
         let asset_server = world.resource::<AssetServer>();
 
-        let rock_texture: Handle<Image> = asset_server.load("hands/rock.png");
-        let paper_texture: Handle<Image> = asset_server.load("hands/paper.png");
-        let scissors_texture: Handle<Image> = asset_server.load("hands/scissors.png");
+        // sprites of the default rules, until the ruleset asset is loaded
+        let sprites = vec![
+            asset_server.load("hands/rock.png"),
+            asset_server.load("hands/paper.png"),
+            asset_server.load("hands/scissors.png"),
+        ];
 
         let mut texture_atlas_layout = world.resource_mut::<Assets<TextureAtlasLayout>>();
 
@@ -99,9 +93,7 @@ impl FromWorld for HandAnimations {
         let atlas_layout = texture_atlas_layout.add(layout);
 
         Self {
-            rock: rock_texture,
-            paper: paper_texture,
-            scissors: scissors_texture,
+            sprites,
             atlas_layout,
             indices,
         }
@@ -109,33 +101,12 @@ impl FromWorld for HandAnimations {
 }
 
 fn sync_hand_animation(
-    mut query: Query<(&Hand, &mut Handle<Image>), Changed<Hand>>,
+    mut query: Query<(Ref<Hand>, &mut Handle<Image>)>,
     animations: Res<HandAnimations>,
 ) {
     for (hand, mut texture) in &mut query.iter_mut() {
-        *texture = animations.get(*hand).clone();
-    }
-}
-
-fn change_hand(
-    mut query: Query<(Entity, Option<&mut EffectSpawner>, &mut Hand)>,
-    input: Res<ButtonInput<KeyCode>>,
-    mut commands: Commands,
-    hana: Res<HanabiThing>,
-    mut trauma: EventWriter<TraumaEvent>,
-) {
-    if input.just_pressed(KeyCode::KeyA) {
-        trauma.send(0.3.into());
-        for (entity, effects, mut hand) in &mut query.iter_mut() {
-            if let Some(mut effects) = effects {
-                effects.reset();
-            } else {
-                commands.entity(entity).insert((
-                    hana.effect().with_z_layer_2d(Some(-0.1)),
-                    CompiledParticleEffect::default(),
-                ));
-            }
-            *hand = hand.cycle();
+        if hand.is_changed() || animations.is_changed() {
+            *texture = animations.get(*hand);
         }
     }
 }
@@ -1,28 +1,39 @@
-use std::time::Duration;
+use std::{f32::consts::PI, time::Duration};
 
 use bevy::{
-    app::{App, Plugin, Startup, Update},
+    app::{App, FixedUpdate, Plugin, Update},
     asset::Assets,
-    color::Color,
     core::Name,
-    ecs::system::SystemId,
     input::ButtonInput,
-    math::{IVec3, Vec3},
+    math::{IVec3, Quat, Rect, Vec2},
     prelude::{
-        info, Commands, Component, Deref, DerefMut, Entity, FromWorld, KeyCode, Mesh, Query,
-        Rectangle, Res, ResMut, Resource, Transform, With, World,
+        default, in_state, Commands, Component, Entity, EventReader, EventWriter,
+        IntoSystemConfigs, Mesh, OnEnter, Query, Rectangle, Res, ResMut, StateScoped, Transform,
+        Visibility, With, Without,
     },
     reflect::Reflect,
     sprite::{ColorMaterial, MaterialMesh2dBundle, Mesh2dHandle},
-    time::Timer,
+    time::{Time, Timer, TimerMode},
 };
-use bevy_tweening::{lens::TransformPositionLens, Animator, EaseFunction, Tween};
+use bevy_hanabi::{CompiledParticleEffect, EffectSpawner};
+use bevy_trauma_shake::TraumaEvent;
+use bevy_tweening::{lens::TransformPositionLens, Animator, EaseFunction, Tween, TweenCompleted};
 
 use crate::{
-    animations::AnimatableSpriteBundle,
-    entity_gc::EntityLifetime,
-    hand::{Hand, HandAnimations, HandBundle},
-    movement::Velocity,
+    actions::{Action, PlayerActions},
+    aim::{AimMode, PointerInput},
+    arena::Arena,
+    combat::Team,
+    fire_pattern::{FirePattern, FirePatternState},
+    hand::Hand,
+    hand_rules::HandRules,
+    health::{Health, Hurtbox, Invulnerable, Lives},
+    particles::HanabiThing,
+    player::PlayerIndex,
+    rng::{GameRng, RngStream},
+    score::Score,
+    state::{has_cannons, GameMode, GameState, InGame},
+    weapon::{tick_weapons, Volley, Weapon, WeaponBundle, WeaponSystems},
 };
 
 #[derive(Reflect)]
@@ -32,17 +43,40 @@ enum CanonState {
 }
 
 #[derive(Component, Reflect)]
-struct HandCannon {
-    fire_rate: Timer,
+pub struct HandCannon {
+    /// type of the next hands fired
+    pub loaded: Hand,
 }
 
-impl HandCannon {
-    fn new(fire_rate: f32) -> Self {
-        let mut timer = Timer::new(
-            Duration::from_millis(fire_rate as u64 / 1000),
-            bevy::time::TimerMode::Repeating,
-        );
-        Self { fire_rate: timer }
+/// End of the arena a cannon fires from
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Facing {
+    /// from the bottom, toward the top
+    #[default]
+    Up,
+    /// from the top, toward the bottom, for the second player of a versus match
+    Down,
+}
+
+impl Facing {
+    /// rotation of a cannon firing straight ahead
+    #[must_use]
+    pub fn rotation(self) -> Quat {
+        match self {
+            Self::Up => Quat::IDENTITY,
+            Self::Down => Quat::from_rotation_z(PI),
+        }
+    }
+
+    /// half of the arena a cannon stays in during a match
+    fn side(self, arena: &Arena) -> Rect {
+        let center = arena.rect.center().y;
+        let mut side = arena.rect;
+        match self {
+            Self::Up => side.max.y = center,
+            Self::Down => side.min.y = center,
+        }
+        side
     }
 }
 
@@ -52,127 +86,321 @@ enum HandCannonState {
     InMotion,
 }
 
-#[derive(Component, Reflect, Deref, DerefMut)]
-struct HandCannonTimer(Timer);
+/// Direction the player has been holding, held directions only repeat once `repeat` is over
+#[derive(Component, Reflect, Debug)]
+struct HeldDirection {
+    direction: IVec3,
+    repeat: Timer,
+    /// the cannon already moved once since the direction was first held
+    moved: bool,
+}
+
+impl Default for HeldDirection {
+    fn default() -> Self {
+        Self {
+            direction: IVec3::ZERO,
+            repeat: Timer::from_seconds(MOVE_REPEAT_DELAY, TimerMode::Once),
+            moved: false,
+        }
+    }
+}
+
+const CANNON_SIZE: Vec2 = Vec2::new(50.0, 100.0);
+const CANNON_HEALTH: u32 = 3;
+const CANNON_LIVES: u32 = 3;
+/// distance between the cannon and its end of the arena when a run starts
+const CANNON_START_MARGIN: f32 = 100.0;
+/// horizontal distance between the cannons of two players when a run starts
+const CANNON_SPACING: f32 = 200.0;
 
-fn spawn_hand_cannon(
+fn spawn_hand_cannons(
     mut commands: Commands,
     mut materials: ResMut<Assets<ColorMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>,
+    arena: Res<Arena>,
+    mode: Res<GameMode>,
 ) {
-    let mesh = Mesh2dHandle(meshes.add(Rectangle::new(50.0, 100.0)));
-    let color = Color::srgb(0.8, 0.5, 0.5);
-    commands.spawn((
-        MaterialMesh2dBundle {
-            mesh,
-            material: materials.add(color),
-            transform: Transform::from_xyz(
-                // Distribute shapes from -X_EXTENT/2 to +X_EXTENT/2.
-                0.0, 0.0, 0.0,
+    let mesh = Mesh2dHandle(meshes.add(Rectangle::from_size(CANNON_SIZE)));
+    let players = mode.players();
+    for player in (0..players).map(PlayerIndex) {
+        // in a match the second player faces the first one from the other end of the arena
+        let (facing, team, position) = if mode.is_match() && player.0 == 1 {
+            (
+                Facing::Down,
+                Team::Enemy,
+                Vec2::new(
+                    arena.rect.center().x,
+                    arena.rect.max.y - CANNON_START_MARGIN,
+                ),
+            )
+        } else {
+            let offset = if mode.is_match() {
+                0.0
+            } else {
+                (player.0 as f32 - (players - 1) as f32 / 2.0) * CANNON_SPACING
+            };
+            (
+                Facing::Up,
+                Team::Player,
+                Vec2::new(
+                    arena.rect.center().x + offset,
+                    arena.rect.min.y + CANNON_START_MARGIN,
+                ),
+            )
+        };
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: mesh.clone(),
+                material: materials.add(player.color()),
+                transform: Transform::from_translation(position.extend(0.0))
+                    .with_rotation(facing.rotation()),
+                ..Default::default()
+            },
+            Name::new("Hand cannon"),
+            StateScoped(InGame),
+            player,
+            facing,
+            HandCannonState::Idle,
+            HeldDirection::default(),
+            HandCannon {
+                loaded: Hand::default(),
+            },
+            team,
+            Score::default(),
+            Health::new(CANNON_HEALTH),
+            Lives(CANNON_LIVES),
+            Hurtbox {
+                half_size: CANNON_SIZE / 2.0,
+            },
+            WeaponBundle::new(
+                Weapon::new(CANNON_COOLDOWN).with_spread(CANNON_SPREAD),
+                FirePattern::default(),
             ),
-            ..Default::default()
-        },
-        Name::new("Hand cannon"),
-        HandCannonState::Idle,
-        HandCannon::new(1.0),
-        // HandCannonTimer(Timer::from_seconds(1.0, TimerMode::Repeating)),
-    ));
+        ));
+    }
 }
 
-fn direction_from_input(input: Res<ButtonInput<KeyCode>>) -> IVec3 {
-    let right = i32::from(input.pressed(KeyCode::ArrowRight));
-    let left = i32::from(input.pressed(KeyCode::ArrowLeft));
-    let up = i32::from(input.pressed(KeyCode::ArrowUp));
-    let down = i32::from(input.pressed(KeyCode::ArrowDown));
+fn direction_from_input(input: &ButtonInput<Action>) -> IVec3 {
+    let right = i32::from(input.pressed(Action::MoveRight));
+    let left = i32::from(input.pressed(Action::MoveLeft));
+    let up = i32::from(input.pressed(Action::MoveUp));
+    let down = i32::from(input.pressed(Action::MoveDown));
 
     IVec3::new(right - left, up - down, 0)
 }
 
-fn clear_movement_state(mut query: Query<&mut HandCannonState>) {
-    let mut state = query.single_mut();
-    *state = HandCannonState::Idle;
+/// `user_data` of the tween events sent when a cannon move is over
+const MOVE_DONE: u64 = 1;
+
+fn finish_moves(
+    mut completed: EventReader<TweenCompleted>,
+    mut query: Query<&mut HandCannonState>,
+) {
+    for event in completed.read() {
+        if event.user_data != MOVE_DONE {
+            continue;
+        }
+        if let Ok(mut state) = query.get_mut(event.entity) {
+            *state = HandCannonState::Idle;
+        }
+    }
+}
+
+/// cannons out of lives stay in place until the run is over, hidden and out of play
+type DefeatedCannon = (With<HandCannon>, Without<Invulnerable>);
+
+fn hide_defeated_cannons(mut query: Query<(&Lives, &mut Visibility), DefeatedCannon>) {
+    for (lives, mut visibility) in &mut query {
+        if **lives == 0 {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+/// The pointer belongs to the first player
+fn uses_pointer(player: PlayerIndex, aim_mode: AimMode) -> bool {
+    player.0 == 0 && aim_mode == AimMode::Pointer
 }
 
+type MovingCannon<'a> = (
+    Entity,
+    &'a PlayerIndex,
+    &'a Facing,
+    &'a Lives,
+    &'a mut HandCannonState,
+    &'a mut HeldDirection,
+    &'a Transform,
+);
+
 const MOVE_DISTANCE: f32 = 100.0;
 const MOVE_SPEED: u64 = 100;
-fn move_cannon(
-    input: Res<ButtonInput<KeyCode>>,
-    mut query: Query<(Entity, &mut HandCannonState, &Transform), With<HandCannon>>,
+/// seconds a direction must be held before the cannon keeps moving that way
+const MOVE_REPEAT_DELAY: f32 = 0.2;
+/// how far the cannon pushes against the edge of the arena before coming back
+const BUMP_DISTANCE: f32 = 15.0;
+#[allow(clippy::too_many_arguments)]
+fn move_cannons(
+    players: Res<PlayerActions>,
+    mode: Res<GameMode>,
+    aim_mode: Res<AimMode>,
+    pointer: Res<PointerInput>,
+    time: Res<Time>,
+    mut query: Query<MovingCannon, With<HandCannon>>,
     mut commands: Commands,
-    clear_movement_state: Option<Res<ClearMovementSystemId>>,
+    arena: Res<Arena>,
 ) {
-    if clear_movement_state.is_none() {
-        return;
-    }
-    if let Ok((entity, mut cannonState, transform)) = query.get_single_mut() {
-        if *cannonState == HandCannonState::InMotion {
-            return;
+    for (entity, player, facing, lives, mut cannon_state, mut held, transform) in &mut query {
+        if **lives == 0 {
+            continue;
+        }
+        let mut direction = direction_from_input(players.get(*player));
+        if uses_pointer(*player, *aim_mode) && direction == IVec3::ZERO {
+            direction = pointer.move_direction(transform.translation.truncate());
+        }
+        if direction == held.direction {
+            held.repeat.tick(time.delta());
+        } else {
+            *held = HeldDirection {
+                direction,
+                ..default()
+            };
+        }
+        if *cannon_state == HandCannonState::InMotion {
+            continue;
+        }
+        if held.moved && !held.repeat.finished() {
+            continue;
         }
-        let direction = direction_from_input(input);
         if direction.length_squared() != 0 {
-            *cannonState = HandCannonState::InMotion;
-            let tween = Tween::new(
-                EaseFunction::ExponentialInOut,
-                Duration::from_millis(MOVE_SPEED),
-                TransformPositionLens {
-                    start: transform.translation,
-                    end: transform.translation + (direction.as_vec3() * MOVE_DISTANCE),
-                },
-            )
-            .with_completed_system(clear_movement_state.unwrap().0);
-            commands.entity(entity).insert(Animator::new(tween));
-        };
+            held.moved = true;
+            *cannon_state = HandCannonState::InMotion;
+            let start = transform.translation;
+            let wanted = start + (direction.as_vec3() * MOVE_DISTANCE);
+            let bounds = if mode.is_match() {
+                Arena {
+                    rect: facing.side(&arena),
+                }
+            } else {
+                *arena
+            };
+            let end = bounds
+                .clamp(wanted.truncate(), CANNON_SIZE / 2.0)
+                .extend(start.z);
+            if end.distance_squared(start) > f32::EPSILON {
+                let tween = Tween::new(
+                    EaseFunction::ExponentialInOut,
+                    Duration::from_millis(MOVE_SPEED),
+                    TransformPositionLens { start, end },
+                )
+                .with_completed_event(MOVE_DONE);
+                commands.entity(entity).insert(Animator::new(tween));
+            } else {
+                // already against the edge, bump into it instead
+                let bump = start + direction.as_vec3().normalize() * BUMP_DISTANCE;
+                let tween = Tween::new(
+                    EaseFunction::QuadraticOut,
+                    Duration::from_millis(MOVE_SPEED / 2),
+                    TransformPositionLens { start, end: bump },
+                )
+                .then(
+                    Tween::new(
+                        EaseFunction::QuadraticIn,
+                        Duration::from_millis(MOVE_SPEED / 2),
+                        TransformPositionLens {
+                            start: bump,
+                            end: start,
+                        },
+                    )
+                    .with_completed_event(MOVE_DONE),
+                );
+                commands.entity(entity).insert(Animator::new(tween));
+            }
+        }
     }
 }
 
-const FIRE_AMOUNT: u32 = 1;
-const FIRE_SPREAD: f32 = 40.0;
-const FIRE_RATE: f32 = 0.5;
+/// seconds between two shots
+const CANNON_COOLDOWN: f32 = 0.15;
+/// radians
+const CANNON_SPREAD: f32 = 0.05;
 
-fn fire_cannon(
-    query: Query<(Entity, &Transform), With<HandCannon>>,
-    hand_animations: Res<HandAnimations>,
-    input: Res<ButtonInput<KeyCode>>,
-    mut commands: Commands,
+type FiringCannon<'a> = (
+    &'a HandCannon,
+    &'a PlayerIndex,
+    &'a Lives,
+    &'a Transform,
+    &'a mut Weapon,
+    &'a FirePattern,
+    &'a mut FirePatternState,
+    &'a mut Volley,
+);
+
+fn fire_cannons(
+    mut query: Query<FiringCannon>,
+    players: Res<PlayerActions>,
+    aim_mode: Res<AimMode>,
+    pointer: Res<PointerInput>,
+    mut rng: ResMut<GameRng>,
 ) {
-    if input.pressed(KeyCode::Space) {
-        let (_, transform) = query.single();
-        for i in 0..FIRE_AMOUNT.pow(2) {
-            let hand = Hand::random();
-            let texture = hand_animations.get(hand);
-            let layout = hand_animations.layout();
-            let indices = hand_animations.indices();
-
-            let pos = Vec3::new(
-                FIRE_SPREAD * ((i / FIRE_AMOUNT) as f32 - ((FIRE_AMOUNT - 1) as f32 / 2.0)),
-                FIRE_SPREAD * ((i % FIRE_AMOUNT) as f32 - ((FIRE_AMOUNT - 1) as f32 / 2.0)),
-                0.0,
-            );
-            commands.spawn((
-                Name::new("Hand"),
-                EntityLifetime::new(5.),
-                HandBundle {
-                    hand,
-                    sprite: AnimatableSpriteBundle::new(
-                        transform.translation + pos,
-                        Vec3::splat(6.0),
-                        texture,
-                        layout,
-                        indices,
-                        0.25,
-                    ),
-                },
-                Velocity::new(0.0, 1000.0, 0.0),
-            ));
+    for (cannon, player, lives, transform, mut weapon, pattern, mut pattern_state, mut volley) in
+        &mut query
+    {
+        let touch_fire =
+            uses_pointer(*player, *aim_mode) && pointer.fires(transform.translation.truncate());
+        if **lives == 0 || !(players.get(*player).pressed(Action::Fire) || touch_fire) {
+            continue;
         }
+        if !weapon.ready() {
+            continue;
+        }
+        weapon.trigger();
+        volley.load(
+            cannon.loaded,
+            pattern.shots(&mut pattern_state, rng.stream(RngStream::PlayerFire)),
+        );
     }
 }
 
-#[derive(Resource, Deref, Debug)]
-struct ClearMovementSystemId(SystemId);
-impl FromWorld for ClearMovementSystemId {
-    fn from_world(world: &mut World) -> Self {
-        Self(world.register_system(clear_movement_state))
+fn change_hands(
+    mut query: Query<(
+        Entity,
+        &PlayerIndex,
+        Option<&mut EffectSpawner>,
+        &mut HandCannon,
+    )>,
+    players: Res<PlayerActions>,
+    mut commands: Commands,
+    hana: Option<Res<HanabiThing>>,
+    rules: Res<HandRules>,
+    mut trauma: EventWriter<TraumaEvent>,
+) {
+    for (entity, player, effects, mut cannon) in &mut query {
+        let input = players.get(*player);
+        let selected = (0..rules.count())
+            .find(|rank| input.just_pressed(Action::SelectHand(*rank)))
+            .map(Hand::new);
+        let loaded = if input.just_pressed(Action::CycleHand) {
+            rules.cycle(cannon.loaded)
+        } else if let Some(hand) = selected {
+            hand
+        } else {
+            continue;
+        };
+        if loaded == cannon.loaded {
+            continue;
+        }
+        cannon.loaded = loaded;
+
+        trauma.send(0.3.into());
+        if let Some(mut effects) = effects {
+            effects.reset();
+        } else if let Some(hana) = &hana {
+            // no particles when running headless
+            commands.entity(entity).insert((
+                hana.effect().with_z_layer_2d(Some(-0.1)),
+                CompiledParticleEffect::default(),
+            ));
+        }
     }
 }
 
@@ -181,8 +409,24 @@ pub struct HandCannonPlugin;
 impl Plugin for HandCannonPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<HandCannonState>()
-            .init_resource::<ClearMovementSystemId>()
-            .add_systems(Update, (move_cannon, fire_cannon))
-            .add_systems(Startup, spawn_hand_cannon);
+            .register_type::<HandCannon>()
+            .register_type::<Facing>()
+            .register_type::<HeldDirection>()
+            .add_systems(
+                Update,
+                (
+                    (finish_moves, move_cannons).chain(),
+                    change_hands,
+                    hide_defeated_cannons,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                FixedUpdate,
+                fire_cannons
+                    .in_set(WeaponSystems::Trigger)
+                    .after(tick_weapons),
+            )
+            .add_systems(OnEnter(InGame), spawn_hand_cannons.run_if(has_cannons));
     }
 }
@@ -0,0 +1,483 @@
+use std::fmt;
+
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::{
+        io::Reader, Asset, AssetApp, AssetEvent, AssetId, AssetLoader, AssetServer, Assets,
+        AsyncReadExt, Handle, LoadContext,
+    },
+    prelude::{EventReader, FromWorld, Image, Res, ResMut, Resource, World},
+    reflect::{Reflect, TypePath},
+};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    config::LaunchOptions,
+    hand::{Hand, HandAnimations, Outcome},
+};
+
+/// Ruleset loaded on startup, can be overridden with `--hand-rules`
+pub const DEFAULT_HAND_RULES: &str = "rules/rps.hands.ron";
+
+/// Hands are stored as an index in a bitmask of the hands they beat
+const MAX_HANDS: usize = u64::BITS as usize;
+
+/// One hand type, as written by designers in `*.hands.ron` files
+#[derive(Deserialize, Debug)]
+struct HandDefinition {
+    name: String,
+    /// path of a 4 frames 32x32 spritesheet, relative to the assets folder
+    sprite: String,
+    /// names of the hands this one beats
+    beats: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct HandRulesDefinition {
+    hands: Vec<HandDefinition>,
+}
+
+/// Hand types and who beats whom
+///
+/// Rules always form a balanced tournament: every pair of hands has exactly one winner, and
+/// every hand beats exactly as many hands as it loses to.
+#[derive(Resource, Reflect, Debug, Clone)]
+pub struct HandRules {
+    names: Vec<String>,
+    /// `beats[a] & (1 << b)` is set when hand `a` beats hand `b`
+    beats: Vec<u64>,
+}
+
+impl Default for HandRules {
+    /// classic rock, paper, scissors
+    fn default() -> Self {
+        Self {
+            names: vec!["Rock".into(), "Paper".into(), "Scissors".into()],
+            beats: vec![0b100, 0b001, 0b010],
+        }
+    }
+}
+
+impl HandRules {
+    /// number of hand types
+    #[must_use]
+    pub const fn count(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn name(&self, hand: Hand) -> &str {
+        self.names.get(hand.index()).map_or("?", String::as_str)
+    }
+
+    pub fn find(&self, name: &str) -> Option<Hand> {
+        self.names.iter().position(|n| n == name).map(Hand::new)
+    }
+
+    #[must_use]
+    pub fn beats(&self, hand: Hand, other: Hand) -> bool {
+        self.beats
+            .get(hand.index())
+            .is_some_and(|beats| beats & (1 << other.index()) != 0)
+    }
+
+    /// outcome of throwing `hand` against `other`, from the point of view of `hand`
+    #[must_use]
+    pub fn versus(&self, hand: Hand, other: Hand) -> Outcome {
+        if self.beats(hand, other) {
+            Outcome::Win
+        } else if self.beats(other, hand) {
+            Outcome::Lose
+        } else {
+            Outcome::Tie
+        }
+    }
+
+    /// next hand in definition order, wrapping around
+    #[must_use]
+    pub fn cycle(&self, hand: Hand) -> Hand {
+        Hand::new((hand.index() + 1) % self.count())
+    }
+
+    pub fn random(&self, rng: &mut impl Rng) -> Hand {
+        Hand::new(rng.gen_range(0..self.count()))
+    }
+
+    fn validate(definition: &HandRulesDefinition) -> Result<Self, HandRulesError> {
+        let hands = &definition.hands;
+        if hands.len() < 3 {
+            return Err(HandRulesError::TooFewHands(hands.len()));
+        }
+        if hands.len() > MAX_HANDS {
+            return Err(HandRulesError::TooManyHands(hands.len()));
+        }
+        if hands.len().is_multiple_of(2) {
+            return Err(HandRulesError::EvenHandCount(hands.len()));
+        }
+
+        let names: Vec<String> = hands.iter().map(|hand| hand.name.clone()).collect();
+        for (index, name) in names.iter().enumerate() {
+            if names[..index].contains(name) {
+                return Err(HandRulesError::DuplicateHand(name.clone()));
+            }
+        }
+
+        let mut beats = vec![0_u64; hands.len()];
+        for (index, hand) in hands.iter().enumerate() {
+            for beaten in &hand.beats {
+                let beaten_index = names.iter().position(|n| n == beaten).ok_or_else(|| {
+                    HandRulesError::UnknownHand {
+                        hand: hand.name.clone(),
+                        beats: beaten.clone(),
+                    }
+                })?;
+                if beaten_index == index {
+                    return Err(HandRulesError::BeatsItself(hand.name.clone()));
+                }
+                beats[index] |= 1 << beaten_index;
+            }
+        }
+
+        let expected_wins = (hands.len() - 1) / 2;
+        for (a, hand) in hands.iter().enumerate() {
+            for b in (a + 1)..hands.len() {
+                let a_beats_b = beats[a] & (1 << b) != 0;
+                let b_beats_a = beats[b] & (1 << a) != 0;
+                if a_beats_b == b_beats_a {
+                    return Err(HandRulesError::AmbiguousPair(
+                        hand.name.clone(),
+                        names[b].clone(),
+                    ));
+                }
+            }
+            let wins = beats[a].count_ones() as usize;
+            if wins != expected_wins {
+                return Err(HandRulesError::Unbalanced {
+                    hand: hand.name.clone(),
+                    wins,
+                    expected: expected_wins,
+                });
+            }
+        }
+
+        Ok(Self { names, beats })
+    }
+}
+
+#[derive(Debug)]
+pub enum HandRulesError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    TooFewHands(usize),
+    TooManyHands(usize),
+    /// a tournament with an even number of players can't be balanced
+    EvenHandCount(usize),
+    DuplicateHand(String),
+    UnknownHand {
+        hand: String,
+        beats: String,
+    },
+    BeatsItself(String),
+    /// both hands beat each other, or neither does
+    AmbiguousPair(String, String),
+    Unbalanced {
+        hand: String,
+        wins: usize,
+        expected: usize,
+    },
+}
+
+impl fmt::Display for HandRulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read hand rules: {err}"),
+            Self::Ron(err) => write!(f, "could not parse hand rules: {err}"),
+            Self::TooFewHands(count) => write!(f, "need at least 3 hands, got {count}"),
+            Self::TooManyHands(count) => {
+                write!(f, "at most {MAX_HANDS} hands are supported, got {count}")
+            }
+            Self::EvenHandCount(count) => {
+                write!(f, "need an odd number of hands to be balanced, got {count}")
+            }
+            Self::DuplicateHand(name) => write!(f, "hand {name} is defined twice"),
+            Self::UnknownHand { hand, beats } => {
+                write!(f, "{hand} beats {beats}, which is not defined")
+            }
+            Self::BeatsItself(name) => write!(f, "{name} beats itself"),
+            Self::AmbiguousPair(a, b) => {
+                write!(f, "exactly one of {a} and {b} must beat the other")
+            }
+            Self::Unbalanced {
+                hand,
+                wins,
+                expected,
+            } => write!(f, "{hand} beats {wins} hands, should beat {expected}"),
+        }
+    }
+}
+
+impl std::error::Error for HandRulesError {}
+
+impl From<std::io::Error> for HandRulesError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for HandRulesError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        Self::Ron(err)
+    }
+}
+
+/// Validated `*.hands.ron` file, along with the sprites of its hands
+#[derive(Asset, TypePath, Debug)]
+pub struct HandRuleSet {
+    pub rules: HandRules,
+    pub sprites: Vec<Handle<Image>>,
+}
+
+#[derive(Default)]
+struct HandRulesLoader;
+
+impl AssetLoader for HandRulesLoader {
+    type Asset = HandRuleSet;
+    type Settings = ();
+    type Error = HandRulesError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<HandRuleSet, HandRulesError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let definition: HandRulesDefinition = ron::de::from_bytes(&bytes)?;
+        let rules = HandRules::validate(&definition)?;
+        let sprites = definition
+            .hands
+            .iter()
+            .map(|hand| load_context.load(hand.sprite.clone()))
+            .collect();
+        Ok(HandRuleSet { rules, sprites })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["hands.ron"]
+    }
+}
+
+/// Ruleset currently in play
+#[derive(Resource, Debug)]
+pub struct ActiveHandRules(Handle<HandRuleSet>);
+
+impl ActiveHandRules {
+    #[must_use]
+    pub fn id(&self) -> AssetId<HandRuleSet> {
+        self.0.id()
+    }
+}
+
+impl FromWorld for ActiveHandRules {
+    fn from_world(world: &mut World) -> Self {
+        let path = world
+            .get_resource::<LaunchOptions>()
+            .and_then(|options| options.hand_rules.clone())
+            .unwrap_or_else(|| DEFAULT_HAND_RULES.to_string());
+        Self(world.resource::<AssetServer>().load(path))
+    }
+}
+
+/// Swap the rules and hand sprites once the active ruleset is (re)loaded
+fn apply_hand_rules(
+    mut events: EventReader<AssetEvent<HandRuleSet>>,
+    active: Res<ActiveHandRules>,
+    rule_sets: Res<Assets<HandRuleSet>>,
+    mut rules: ResMut<HandRules>,
+    mut animations: ResMut<HandAnimations>,
+) {
+    for event in events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        if *id != active.0.id() {
+            continue;
+        }
+        if let Some(rule_set) = rule_sets.get(*id) {
+            rules.clone_from(&rule_set.rules);
+            animations.set_sprites(rule_set.sprites.clone());
+        }
+    }
+}
+
+pub struct HandRulesPlugin;
+
+impl Plugin for HandRulesPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<HandRules>()
+            .init_asset::<HandRuleSet>()
+            .init_asset_loader::<HandRulesLoader>()
+            .init_resource::<HandRules>()
+            .add_systems(Update, apply_hand_rules);
+    }
+
+    fn finish(&self, app: &mut App) {
+        // after every plugin is built, so a replay had a chance to pick the ruleset
+        app.init_resource::<ActiveHandRules>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<HandRules, HandRulesError> {
+        let definition: HandRulesDefinition = ron::de::from_str(source)?;
+        HandRules::validate(&definition)
+    }
+
+    /// rules from `(name, beats)` pairs, every hand using the same sprite
+    fn rules(hands: &[(&str, &[&str])]) -> Result<HandRules, HandRulesError> {
+        let definition = HandRulesDefinition {
+            hands: hands
+                .iter()
+                .map(|(name, beats)| HandDefinition {
+                    name: (*name).to_string(),
+                    sprite: "hands/rock.png".to_string(),
+                    beats: beats.iter().map(|beaten| (*beaten).to_string()).collect(),
+                })
+                .collect(),
+        };
+        HandRules::validate(&definition)
+    }
+
+    fn assert_balanced(rules: &HandRules) {
+        let wins = (rules.count() - 1) / 2;
+        for a in (0..rules.count()).map(Hand::new) {
+            assert_eq!(rules.versus(a, a), Outcome::Tie);
+            let beaten = (0..rules.count())
+                .map(Hand::new)
+                .filter(|b| rules.beats(a, *b))
+                .count();
+            let name = &rules.names[a.index()];
+            assert_eq!(beaten, wins, "{name} beats {beaten} hands");
+        }
+    }
+
+    #[test]
+    fn standard_rule_sets_are_valid() {
+        for (source, count) in [
+            (include_str!("../assets/rules/rps.hands.ron"), 3),
+            (include_str!("../assets/rules/rpsls.hands.ron"), 5),
+            (include_str!("../assets/rules/rps7.hands.ron"), 7),
+        ] {
+            let rules = parse(source).unwrap();
+            assert_eq!(rules.count(), count);
+            assert_balanced(&rules);
+        }
+    }
+
+    #[test]
+    fn rps_file_matches_the_default_rules() {
+        let rules = parse(include_str!("../assets/rules/rps.hands.ron")).unwrap();
+        let default = HandRules::default();
+        assert_eq!(rules.names, default.names);
+        assert_eq!(rules.beats, default.beats);
+        let [rock, paper, scissors] = [0, 1, 2].map(Hand::new);
+        assert_eq!(rules.versus(rock, scissors), Outcome::Win);
+        assert_eq!(rules.versus(rock, paper), Outcome::Lose);
+        assert_eq!(rules.versus(paper, scissors), Outcome::Lose);
+    }
+
+    #[test]
+    fn rejects_unparsable_files() {
+        assert!(matches!(parse("(hands: ["), Err(HandRulesError::Ron(_))));
+    }
+
+    #[test]
+    fn rejects_too_few_hands() {
+        let result = rules(&[("Rock", &["Paper"]), ("Paper", &[])]);
+        assert!(matches!(result, Err(HandRulesError::TooFewHands(2))));
+    }
+
+    #[test]
+    fn rejects_too_many_hands() {
+        let names: Vec<String> = (0..=MAX_HANDS).map(|i| format!("Hand {i}")).collect();
+        let hands: Vec<(&str, &[&str])> = names.iter().map(|n| (n.as_str(), &[][..])).collect();
+        let result = rules(&hands);
+        assert!(
+            matches!(result, Err(HandRulesError::TooManyHands(count)) if count == MAX_HANDS + 1)
+        );
+    }
+
+    #[test]
+    fn rejects_even_hand_counts() {
+        let result = rules(&[("A", &["B"]), ("B", &["C"]), ("C", &["D"]), ("D", &["A"])]);
+        assert!(matches!(result, Err(HandRulesError::EvenHandCount(4))));
+    }
+
+    #[test]
+    fn rejects_duplicate_hands() {
+        let result = rules(&[
+            ("Rock", &["Scissors"]),
+            ("Rock", &["Rock"]),
+            ("Scissors", &["Paper"]),
+        ]);
+        assert!(matches!(result, Err(HandRulesError::DuplicateHand(name)) if name == "Rock"));
+    }
+
+    #[test]
+    fn rejects_unknown_hands() {
+        let result = rules(&[
+            ("Rock", &["Scissor"]),
+            ("Paper", &["Rock"]),
+            ("Scissors", &["Paper"]),
+        ]);
+        assert!(matches!(
+            result,
+            Err(HandRulesError::UnknownHand { hand, beats }) if hand == "Rock" && beats == "Scissor"
+        ));
+    }
+
+    #[test]
+    fn rejects_hands_beating_themselves() {
+        let result = rules(&[
+            ("Rock", &["Rock"]),
+            ("Paper", &["Rock"]),
+            ("Scissors", &["Paper"]),
+        ]);
+        assert!(matches!(result, Err(HandRulesError::BeatsItself(name)) if name == "Rock"));
+    }
+
+    #[test]
+    fn rejects_ambiguous_pairs() {
+        // rock and paper beat each other
+        let result = rules(&[
+            ("Rock", &["Paper"]),
+            ("Paper", &["Rock"]),
+            ("Scissors", &["Paper"]),
+        ]);
+        assert!(matches!(
+            result,
+            Err(HandRulesError::AmbiguousPair(a, b)) if a == "Rock" && b == "Paper"
+        ));
+    }
+
+    #[test]
+    fn rejects_unbalanced_rules() {
+        // every pair has a single winner, but A beats everything
+        let result = rules(&[
+            ("A", &["B", "C", "D", "E"]),
+            ("B", &["C", "D"]),
+            ("C", &["D", "E"]),
+            ("D", &["E"]),
+            ("E", &["B"]),
+        ]);
+        assert!(matches!(
+            result,
+            Err(HandRulesError::Unbalanced { hand, wins: 4, expected: 2 }) if hand == "A"
+        ));
+    }
+}
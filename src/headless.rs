@@ -0,0 +1,63 @@
+use bevy::{
+    app::{App, Plugin},
+    asset::{io::Reader, AssetApp, AssetLoader, AssetPlugin, LoadContext},
+    hierarchy::HierarchyPlugin,
+    input::InputPlugin,
+    log::LogPlugin,
+    render::{mesh::Mesh, texture::Image},
+    sprite::{ColorMaterial, TextureAtlasLayout},
+    state::app::StatesPlugin,
+    transform::TransformPlugin,
+    MinimalPlugins,
+};
+use bevy_trauma_shake::TraumaEvent;
+
+/// Stands in for the png loader, which needs a GPU, with blank images
+#[derive(Default)]
+struct PlaceholderImageLoader;
+
+impl AssetLoader for PlaceholderImageLoader {
+    type Asset = Image;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load<'a>(
+        &'a self,
+        _reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Image, std::io::Error> {
+        Ok(Image::default())
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["png"]
+    }
+}
+
+/// Engine plugins needed to run the game logic without a window, a GPU or audio
+///
+/// Replaces `DefaultPlugins` and every presentation plugin (camera, particles, HUD) for CI and
+/// batch simulations, inputs come from a replay given with `--replay`.
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            MinimalPlugins,
+            LogPlugin::default(),
+            StatesPlugin,
+            AssetPlugin::default(),
+            InputPlugin,
+            TransformPlugin,
+            HierarchyPlugin,
+        ))
+        // assets and events the game logic refers to, normally set up by the render plugins
+        .init_asset::<Image>()
+        .init_asset::<TextureAtlasLayout>()
+        .init_asset::<Mesh>()
+        .init_asset::<ColorMaterial>()
+        .init_asset_loader::<PlaceholderImageLoader>()
+        .add_event::<TraumaEvent>();
+    }
+}
@@ -0,0 +1,310 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{App, FixedUpdate, Plugin, Update},
+    math::Vec2,
+    prelude::{
+        in_state, Commands, Component, Deref, DerefMut, Entity, Event, EventWriter,
+        IntoSystemConfigs, NextState, OnEnter, Query, Res, ResMut, Transform, Visibility, With,
+        Without,
+    },
+    reflect::Reflect,
+    time::{Time, Timer, TimerMode},
+};
+use bevy_trauma_shake::TraumaEvent;
+
+use crate::{
+    combat::{Team, HAND_RADIUS},
+    hand::Hand,
+    pool::{Dormant, Retire},
+    simulation::SimulationSystems,
+    state::GameState,
+};
+
+/// seconds of invulnerability after being hit
+const INVULNERABILITY: f32 = 1.5;
+/// the sprite of invulnerable things is shown and hidden for this long in turn
+const BLINK_INTERVAL: Duration = Duration::from_millis(100);
+const HIT_TRAUMA: f32 = 0.5;
+
+/// Hits something can take before losing a life
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Health {
+    #[must_use]
+    pub const fn new(max: u32) -> Self {
+        Self { current: max, max }
+    }
+}
+
+/// Lives left, the game is over once everything with lives has none left
+#[derive(Component, Reflect, Debug, Clone, Copy, Deref, DerefMut)]
+pub struct Lives(pub u32);
+
+/// Area where hands of the other [`Team`] deal damage, centered on the transform
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+pub struct Hurtbox {
+    pub half_size: Vec2,
+}
+
+/// Ignores hits until the timer is over
+#[derive(Component, Reflect, Debug, Deref, DerefMut)]
+pub struct Invulnerable(Timer);
+
+impl Invulnerable {
+    #[must_use]
+    pub fn new(duration: f32) -> Self {
+        Self(Timer::from_seconds(duration, TimerMode::Once))
+    }
+}
+
+/// Sent every time a hand gets through to something with [`Health`]
+#[derive(Event, Debug)]
+pub struct Hit {
+    pub target: Entity,
+    pub hand: Entity,
+    /// the hit took the last point of health, and a life with it
+    pub life_lost: bool,
+}
+
+type Target<'a> = (
+    Entity,
+    &'a Transform,
+    &'a Hurtbox,
+    &'a Team,
+    &'a mut Health,
+    &'a mut Lives,
+);
+
+/// hands still in play, pooled ones waiting to be reused can't hit anything
+type InPlayHand = (With<Hand>, Without<Dormant>);
+
+fn take_hits(
+    mut targets: Query<Target, Without<Invulnerable>>,
+    hands: Query<(Entity, &Transform, &Team), InPlayHand>,
+    mut hits: EventWriter<Hit>,
+    mut trauma: EventWriter<TraumaEvent>,
+    mut commands: Commands,
+) {
+    for (target, target_transform, hurtbox, team, mut health, mut lives) in &mut targets {
+        if **lives == 0 {
+            continue;
+        }
+        let center = target_transform.translation.truncate();
+        let Some((hand, _, _)) = hands.iter().find(|(_, transform, hand_team)| {
+            let position = transform.translation.truncate();
+            let closest = position.clamp(center - hurtbox.half_size, center + hurtbox.half_size);
+            let radius = HAND_RADIUS * transform.scale.x;
+            *hand_team != team && closest.distance_squared(position) <= radius * radius
+        }) else {
+            continue;
+        };
+
+        commands.add(Retire(hand));
+        health.current = health.current.saturating_sub(1);
+        let life_lost = health.current == 0;
+        if life_lost {
+            **lives -= 1;
+            health.current = health.max;
+        }
+        commands
+            .entity(target)
+            .insert(Invulnerable::new(INVULNERABILITY));
+        trauma.send(HIT_TRAUMA.into());
+        hits.send(Hit {
+            target,
+            hand,
+            life_lost,
+        });
+    }
+}
+
+fn tick_invulnerability(
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Invulnerable, &mut Visibility)>,
+    mut commands: Commands,
+) {
+    for (entity, mut invulnerable, mut visibility) in &mut query {
+        invulnerable.tick(time.delta());
+        if invulnerable.finished() {
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<Invulnerable>();
+        }
+    }
+}
+
+fn blink_invulnerable(mut query: Query<(&Invulnerable, &mut Visibility)>) {
+    for (invulnerable, mut visibility) in &mut query {
+        let phase = invulnerable.elapsed().as_millis() / BLINK_INTERVAL.as_millis();
+        *visibility = if phase.is_multiple_of(2) {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
+
+/// Blinking stops while paused, whatever was hidden is shown until it starts again
+fn show_invulnerable(mut query: Query<&mut Visibility, With<Invulnerable>>) {
+    for mut visibility in &mut query {
+        *visibility = Visibility::Inherited;
+    }
+}
+
+fn game_over_without_lives(lives: Query<&Lives>, mut next_state: ResMut<NextState<GameState>>) {
+    if !lives.is_empty() && lives.iter().all(|lives| **lives == 0) {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Health>()
+            .register_type::<Lives>()
+            .register_type::<Hurtbox>()
+            .register_type::<Invulnerable>()
+            .add_event::<Hit>()
+            .add_systems(
+                FixedUpdate,
+                (tick_invulnerability, take_hits, game_over_without_lives)
+                    .chain()
+                    .in_set(SimulationSystems::React),
+            )
+            .add_systems(
+                Update,
+                blink_invulnerable.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnEnter(GameState::Paused), show_invulnerable);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::system::RunSystemOnce, prelude::Events};
+
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .add_event::<Hit>()
+            .add_event::<TraumaEvent>()
+            .add_systems(Update, (tick_invulnerability, take_hits).chain());
+        app
+    }
+
+    fn spawn_cannon(app: &mut App, health: u32) -> Entity {
+        app.world_mut()
+            .spawn((
+                Transform::default(),
+                Hurtbox {
+                    half_size: Vec2::splat(50.0),
+                },
+                Team::Player,
+                Health::new(health),
+                Lives(3),
+                Visibility::default(),
+            ))
+            .id()
+    }
+
+    /// a hand right on top of the cannon
+    fn spawn_hand(app: &mut App, team: Team) -> Entity {
+        app.world_mut()
+            .spawn((Hand::new(0), Transform::default(), team))
+            .id()
+    }
+
+    fn hits(app: &mut App) -> Vec<Hit> {
+        app.update();
+        app.world_mut()
+            .resource_mut::<Events<Hit>>()
+            .drain()
+            .collect()
+    }
+
+    fn advance(app: &mut App, seconds: f32) {
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(seconds));
+    }
+
+    #[test]
+    fn only_hands_of_the_other_team_hit() {
+        let mut app = app();
+        let cannon = spawn_cannon(&mut app, 3);
+        let own = spawn_hand(&mut app, Team::Player);
+        assert!(hits(&mut app).is_empty());
+
+        let enemy = spawn_hand(&mut app, Team::Enemy);
+        let hits = hits(&mut app);
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].target, hits[0].hand), (cannon, enemy));
+        assert_eq!(app.world().get::<Health>(cannon).unwrap().current, 2);
+        // the hand that hit is gone, the other one is still there
+        assert!(app.world().get_entity(enemy).is_none());
+        assert!(app.world().get_entity(own).is_some());
+    }
+
+    #[test]
+    fn dormant_hands_do_not_hit() {
+        let mut app = app();
+        spawn_cannon(&mut app, 3);
+        let hand = spawn_hand(&mut app, Team::Enemy);
+        app.world_mut().entity_mut(hand).insert(Dormant);
+        assert!(hits(&mut app).is_empty());
+    }
+
+    #[test]
+    fn hits_are_ignored_while_invulnerable() {
+        let mut app = app();
+        let cannon = spawn_cannon(&mut app, 3);
+        spawn_hand(&mut app, Team::Enemy);
+        assert_eq!(hits(&mut app).len(), 1);
+        assert!(app.world().get::<Invulnerable>(cannon).is_some());
+
+        spawn_hand(&mut app, Team::Enemy);
+        advance(&mut app, INVULNERABILITY / 2.0);
+        assert!(hits(&mut app).is_empty());
+
+        advance(&mut app, INVULNERABILITY);
+        assert_eq!(hits(&mut app).len(), 1);
+        assert_eq!(app.world().get::<Health>(cannon).unwrap().current, 1);
+        assert_eq!(
+            app.world().get::<Visibility>(cannon),
+            Some(&Visibility::Inherited)
+        );
+    }
+
+    #[test]
+    fn pausing_shows_blinking_cannons() {
+        let mut app = app();
+        let cannon = spawn_cannon(&mut app, 3);
+        app.world_mut()
+            .entity_mut(cannon)
+            .insert((Invulnerable::new(INVULNERABILITY), Visibility::Hidden));
+        app.world_mut().run_system_once(show_invulnerable);
+        assert_eq!(
+            app.world().get::<Visibility>(cannon),
+            Some(&Visibility::Inherited)
+        );
+    }
+
+    #[test]
+    fn last_point_of_health_takes_a_life() {
+        let mut app = app();
+        let cannon = spawn_cannon(&mut app, 1);
+        spawn_hand(&mut app, Team::Enemy);
+        let hits = hits(&mut app);
+        assert!(hits[0].life_lost);
+        assert_eq!(**app.world().get::<Lives>(cannon).unwrap(), 2);
+        // back to full health for the next life
+        assert_eq!(app.world().get::<Health>(cannon).unwrap().current, 1);
+    }
+}
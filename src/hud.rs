@@ -0,0 +1,409 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{App, Plugin, Update},
+    color::{Alpha, Color},
+    core::Name,
+    math::Vec3,
+    prelude::{
+        default, BuildChildren, Bundle, Commands, Component, DetectChanges, Entity, EventReader,
+        ImageBundle, IntoSystemConfigs, NodeBundle, OnEnter, Query, Ref, Res, StateScoped, Text,
+        TextBundle, With, Without,
+    },
+    sprite::TextureAtlas,
+    text::TextStyle,
+    ui::{AlignItems, BackgroundColor, FlexDirection, PositionType, Style, UiImage, UiRect, Val},
+};
+use bevy_tweening::{
+    lens::{TextColorLens, TransformScaleLens},
+    Animator, EaseFunction, Tween,
+};
+
+use crate::{
+    animations::AnimationTimer,
+    hand::{Hand, HandAnimations},
+    hand_cannon::HandCannon,
+    hand_rules::HandRules,
+    health::{Health, Hit, Lives},
+    player::PlayerIndex,
+    rounds::Match,
+    score::{ComboBroken, ComboExtended, ComboStarted, Score},
+    state::{has_cannons, GameMode, InGame},
+};
+
+/// how many of the hands coming after the loaded one are previewed
+const NEXT_HANDS: usize = 2;
+const FLASH_DURATION: Duration = Duration::from_millis(300);
+const SCORE_FLASH: Color = Color::srgb(1.0, 0.85, 0.2);
+const HIT_FLASH: Color = Color::srgb(1.0, 0.2, 0.2);
+/// horizontal distance between the score panels of two players, in percent of the window
+const SCORE_PANEL_SPACING: f32 = 15.0;
+
+/// Sprite of the hand currently loaded in the cannon
+#[derive(Component)]
+struct AmmoIcon;
+
+/// Name of the hand currently loaded in the cannon
+#[derive(Component)]
+struct AmmoLabel;
+
+/// Sprite of the hand loaded after cycling this many times
+#[derive(Component)]
+struct NextHandIcon(usize);
+
+#[derive(Component)]
+struct ScoreLabel;
+
+/// Rounds won by each player, in a match
+#[derive(Component)]
+struct MatchLabel;
+
+/// Current combo and multiplier
+#[derive(Component, Default)]
+struct ComboLabel {
+    /// wins in a row, 0 outside of a combo
+    combo: u32,
+}
+
+/// Lives and health of the cannon
+#[derive(Component)]
+struct LivesLabel;
+
+fn text_style(font_size: f32) -> TextStyle {
+    TextStyle {
+        font_size,
+        color: Color::WHITE,
+        ..default()
+    }
+}
+
+fn panel(name: &'static str, style: Style) -> (Name, StateScoped<InGame>, NodeBundle) {
+    (
+        Name::new(name),
+        StateScoped(InGame),
+        NodeBundle {
+            background_color: BackgroundColor(Color::BLACK.with_alpha(0.5)),
+            style: Style {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(4.0)),
+                ..style
+            },
+            ..default()
+        },
+    )
+}
+
+fn hand_icon(hand_animations: &HandAnimations, size: f32) -> impl Bundle {
+    (
+        ImageBundle {
+            style: Style {
+                width: Val::Px(size),
+                height: Val::Px(size),
+                ..default()
+            },
+            ..default()
+        },
+        TextureAtlas {
+            layout: hand_animations.layout(),
+            index: hand_animations.indices().first,
+        },
+        hand_animations.indices(),
+    )
+}
+
+/// One score panel and one ammo indicator for every player
+fn spawn_hud(mut commands: Commands, hand_animations: Res<HandAnimations>, mode: Res<GameMode>) {
+    for player in (0..mode.players()).map(PlayerIndex) {
+        spawn_player_hud(&mut commands, &hand_animations, player, mode.players() > 1);
+    }
+    if mode.is_match() {
+        commands
+            .spawn(panel(
+                "Match panel",
+                Style {
+                    // top center, between the score panels and the debug counters
+                    left: Val::Percent(45.),
+                    top: Val::Percent(1.),
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    MatchLabel,
+                    Name::new("MatchLabel"),
+                    TextBundle::from_section("", text_style(24.0)),
+                ));
+            });
+    }
+}
+
+fn spawn_player_hud(
+    commands: &mut Commands,
+    hand_animations: &HandAnimations,
+    player: PlayerIndex,
+    named: bool,
+) {
+    commands
+        .spawn(panel(
+            "Score panel",
+            Style {
+                // top-left corner, the debug counters are on the right
+                left: Val::Percent(SCORE_PANEL_SPACING.mul_add(player.0 as f32, 1.)),
+                top: Val::Percent(1.),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            if named {
+                parent.spawn((
+                    Name::new("PlayerLabel"),
+                    TextBundle::from_section(
+                        format!("P{}", player.0 + 1),
+                        TextStyle {
+                            color: player.color(),
+                            ..text_style(20.0)
+                        },
+                    ),
+                ));
+            }
+            parent.spawn((
+                ScoreLabel,
+                player,
+                Name::new("ScoreLabel"),
+                TextBundle::from_section("0", text_style(32.0)),
+            ));
+            parent.spawn((
+                ComboLabel::default(),
+                player,
+                Name::new("ComboLabel"),
+                TextBundle::from_section("", text_style(20.0)),
+            ));
+            parent.spawn((
+                LivesLabel,
+                player,
+                Name::new("LivesLabel"),
+                TextBundle::from_section("", text_style(20.0)),
+            ));
+        });
+
+    // bottom corners, away from the action at the top
+    let (left, right) = if player.0 == 0 {
+        (Val::Percent(1.), Val::Auto)
+    } else {
+        (Val::Auto, Val::Percent(1.))
+    };
+    commands
+        .spawn(panel(
+            "Ammo indicator",
+            Style {
+                left,
+                right,
+                bottom: Val::Percent(1.),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                AmmoIcon,
+                player,
+                Name::new("AmmoIcon"),
+                hand_icon(hand_animations, 96.0),
+                AnimationTimer::repeating(0.25),
+            ));
+            parent.spawn((
+                AmmoLabel,
+                player,
+                Name::new("AmmoLabel"),
+                TextBundle::from_section("", text_style(24.0)),
+            ));
+            parent
+                .spawn((
+                    Name::new("Next hands"),
+                    NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section("next", text_style(16.0)));
+                    for ahead in 1..=NEXT_HANDS {
+                        parent.spawn((
+                            NextHandIcon(ahead),
+                            player,
+                            Name::new("NextHandIcon"),
+                            hand_icon(hand_animations, 40.0),
+                        ));
+                    }
+                });
+        });
+}
+
+fn flash(color: Color) -> Animator<Text> {
+    Animator::new(Tween::new(
+        EaseFunction::QuadraticOut,
+        FLASH_DURATION,
+        TextColorLens {
+            start: color,
+            end: Color::WHITE,
+            section: 0,
+        },
+    ))
+}
+
+fn update_score_labels(
+    scores: Query<(&PlayerIndex, Ref<Score>)>,
+    mut score_labels: Query<(Entity, &PlayerIndex, &mut Text), With<ScoreLabel>>,
+    mut commands: Commands,
+) {
+    for (player, score) in &scores {
+        if !score.is_changed() {
+            continue;
+        }
+        let points = score.points.to_string();
+        for (entity, _, mut label) in score_labels
+            .iter_mut()
+            .filter(|(_, index, _)| *index == player)
+        {
+            let gained = label.sections[0].value != points && !score.is_added();
+            if gained {
+                commands.entity(entity).insert(flash(SCORE_FLASH));
+            }
+            label.sections[0].value.clone_from(&points);
+        }
+    }
+}
+
+/// Combos come from their events, the multiplier from the score as it decays every frame
+fn update_combo_labels(
+    mut started: EventReader<ComboStarted>,
+    mut extended: EventReader<ComboExtended>,
+    mut broken: EventReader<ComboBroken>,
+    scores: Query<(&PlayerIndex, &Score)>,
+    mut labels: Query<(&PlayerIndex, &mut ComboLabel, &mut Text)>,
+) {
+    let combos = started
+        .read()
+        .map(|event| (event.player, event.combo))
+        .chain(extended.read().map(|event| (event.player, event.combo)))
+        .chain(broken.read().map(|event| (event.player, 0)));
+    for (player, combo) in combos {
+        for (_, mut label, _) in labels.iter_mut().filter(|(index, ..)| **index == player) {
+            label.combo = combo;
+        }
+    }
+    for (player, label, mut text) in &mut labels {
+        let Some((_, score)) = scores.iter().find(|(index, _)| *index == player) else {
+            continue;
+        };
+        text.sections[0].value = if label.combo > 0 {
+            format!("combo {}  x{:.1}", label.combo, score.multiplier)
+        } else {
+            format!("x{:.1}", score.multiplier)
+        };
+    }
+}
+
+fn update_match_label(
+    current: Res<Match>,
+    mode: Res<GameMode>,
+    mut labels: Query<(Ref<MatchLabel>, &mut Text)>,
+) {
+    for (marker, mut label) in &mut labels {
+        if current.is_changed() || marker.is_added() {
+            label.sections[0].value =
+                format!("{}  best of {}", current.tally(*mode), current.best_of);
+        }
+    }
+}
+
+fn update_lives_labels(
+    cannons: Query<(Entity, &PlayerIndex, Ref<Health>, &Lives), With<HandCannon>>,
+    mut labels: Query<(Entity, &PlayerIndex, &mut Text), With<LivesLabel>>,
+    mut hits: EventReader<Hit>,
+    mut commands: Commands,
+) {
+    let hit_targets: Vec<Entity> = hits.read().map(|hit| hit.target).collect();
+    for (cannon, player, health, lives) in &cannons {
+        if !health.is_changed() {
+            continue;
+        }
+        let hit = hit_targets.contains(&cannon);
+        for (entity, _, mut label) in labels.iter_mut().filter(|(_, index, _)| *index == player) {
+            let remaining = health.current as usize;
+            let lost = health.max.saturating_sub(health.current) as usize;
+            // ASCII only, the default font has no box glyphs
+            label.sections[0].value = format!(
+                "lives {}  [{}{}]",
+                **lives,
+                "#".repeat(remaining),
+                "-".repeat(lost)
+            );
+            if hit {
+                commands.entity(entity).insert(flash(HIT_FLASH));
+            }
+        }
+    }
+}
+
+fn update_ammo_indicators(
+    cannons: Query<(&PlayerIndex, Ref<HandCannon>)>,
+    mut icons: Query<(Entity, &PlayerIndex, &mut UiImage), With<AmmoIcon>>,
+    mut next_icons: Query<(&NextHandIcon, &PlayerIndex, &mut UiImage), Without<AmmoIcon>>,
+    mut labels: Query<(&PlayerIndex, &mut Text), With<AmmoLabel>>,
+    hand_animations: Res<HandAnimations>,
+    rules: Res<HandRules>,
+    mut commands: Commands,
+) {
+    for (player, cannon) in &cannons {
+        if !(cannon.is_changed() || hand_animations.is_changed() || rules.is_changed()) {
+            continue;
+        }
+        for (entity, _, mut icon) in icons.iter_mut().filter(|(_, index, _)| *index == player) {
+            icon.texture = hand_animations.get(cannon.loaded);
+            if cannon.is_changed() && !cannon.is_added() {
+                commands.entity(entity).insert(Animator::new(Tween::new(
+                    EaseFunction::BackOut,
+                    FLASH_DURATION,
+                    TransformScaleLens {
+                        start: Vec3::splat(1.3),
+                        end: Vec3::ONE,
+                    },
+                )));
+            }
+        }
+        for (NextHandIcon(ahead), _, mut icon) in next_icons
+            .iter_mut()
+            .filter(|(_, index, _)| *index == player)
+        {
+            let hand = (0..*ahead).fold(cannon.loaded, |hand: Hand, _| rules.cycle(hand));
+            icon.texture = hand_animations.get(hand);
+        }
+        for (_, mut label) in labels.iter_mut().filter(|(index, _)| *index == player) {
+            label.sections[0].value = rules.name(cannon.loaded).to_string();
+        }
+    }
+}
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(InGame), spawn_hud.run_if(has_cannons))
+            .add_systems(
+                Update,
+                (
+                    update_score_labels,
+                    update_combo_labels,
+                    update_lives_labels,
+                    update_match_label,
+                    update_ammo_indicators,
+                ),
+            );
+    }
+}
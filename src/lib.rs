@@ -0,0 +1,100 @@
+#![deny(clippy::all)]
+#![warn(clippy::pedantic)]
+#![warn(clippy::nursery)]
+#![allow(clippy::needless_pass_by_value)]
+#![allow(clippy::module_name_repetitions)]
+#![allow(clippy::cast_precision_loss)]
+
+pub mod actions;
+pub mod aim;
+pub mod animations;
+pub mod arena;
+pub mod camera;
+pub mod combat;
+pub mod config;
+#[cfg(feature = "debug")]
+pub mod debug;
+pub mod duel;
+pub mod entity_gc;
+pub mod fire_pattern;
+pub mod hand;
+pub mod hand_cannon;
+pub mod hand_rules;
+pub mod headless;
+pub mod health;
+pub mod hud;
+pub mod menu;
+pub mod movement;
+pub mod particles;
+pub mod player;
+pub mod pool;
+pub mod replay;
+pub mod rng;
+pub mod rounds;
+pub mod score;
+pub mod simulation;
+pub mod state;
+pub mod waves;
+pub mod weapon;
+
+use bevy::app::{PluginGroup, PluginGroupBuilder};
+use bevy_framepace::FramepacePlugin;
+use bevy_tweening::TweeningPlugin;
+
+pub use animations::AnimatableSpriteBundle;
+pub use config::LaunchOptions;
+pub use entity_gc::EntityLifetime;
+pub use hand::Hand;
+pub use headless::HeadlessPlugin;
+pub use movement::Velocity;
+
+/// Game logic, runs on top of either `DefaultPlugins` or the [`HeadlessPlugin`]
+///
+/// Reads the [`LaunchOptions`] resource if it is inserted before the group is added.
+pub struct RpsGamePlugins;
+
+impl PluginGroup for RpsGamePlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(state::StatePlugin)
+            .add(player::PlayerPlugin)
+            .add(actions::ActionsPlugin)
+            .add(aim::AimPlugin)
+            .add(menu::MenuPlugin)
+            .add(hand::HandPlugin)
+            .add(hand_rules::HandRulesPlugin)
+            .add(TweeningPlugin)
+            .add(movement::MovementPlugin)
+            .add(hand_cannon::HandCannonPlugin)
+            .add(entity_gc::EntityGcPlugin)
+            .add(arena::ArenaPlugin)
+            .add(combat::CombatPlugin)
+            .add(waves::WavesPlugin)
+            .add(weapon::WeaponPlugin)
+            .add(pool::PoolPlugin)
+            .add(rng::RngPlugin)
+            .add(score::ScorePlugin)
+            .add(health::HealthPlugin)
+            .add(rounds::RoundsPlugin)
+            .add(duel::DuelPlugin)
+            .add(replay::ReplayPlugin)
+            .add(simulation::SimulationPlugin)
+    }
+}
+
+/// Camera, particles, sprite animations and HUD, needs `DefaultPlugins`
+pub struct RpsPresentationPlugins;
+
+impl PluginGroup for RpsPresentationPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        let group = PluginGroupBuilder::start::<Self>()
+            .add(camera::CameraPlugin)
+            .add(FramepacePlugin)
+            .add(particles::ParticlesPlugin)
+            .add(animations::AnimationsPlugin)
+            .add(hud::HudPlugin);
+        #[cfg(feature = "debug")]
+        let group = group.add(debug::DebugPlugin);
+        group
+    }
+}
@@ -0,0 +1,524 @@
+use bevy::{
+    app::{App, AppExit, Plugin, PreUpdate, Update},
+    color::{Alpha, Color},
+    core::Name,
+    input::{gamepad::GamepadButton, keyboard::KeyCode, ButtonInput},
+    prelude::{
+        default, in_state, not, AppExtStates, BuildChildren, ButtonBundle, Changed, Commands,
+        Component, DetectChanges, DetectChangesMut, EventWriter, IntoSystemConfigs, NextState,
+        NodeBundle, OnEnter, OnExit, Query, Ref, Res, ResMut, Resource, State, StateScoped, States,
+        Text, TextBundle,
+    },
+    text::TextStyle,
+    ui::{
+        AlignItems, BackgroundColor, FlexDirection, Interaction, JustifyContent, Style, UiRect,
+        UiSystem, Val, ZIndex,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::{Action, ActionSystems, Binding, Bindings},
+    aim::AimMode,
+    combat::TieRule,
+    player::{PlayerIndex, MAX_PLAYERS},
+    rounds::{Match, BEST_OF_CHOICES},
+    state::{GameMode, GameState},
+};
+
+const BUTTON_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
+const FOCUSED_BUTTON_COLOR: Color = Color::srgb(0.8, 0.5, 0.5);
+
+/// Menu currently on screen
+#[derive(States, Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum MenuScreen {
+    #[default]
+    Closed,
+    Main,
+    Modes,
+    Settings,
+    /// bindings of the actions, see [`Bindings`]
+    Controls,
+    Pause,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum MenuAction {
+    /// start a run in the current mode
+    Play,
+    PlayMode(GameMode),
+    Modes,
+    Settings,
+    Controls,
+    Quit,
+    Resume,
+    Restart,
+    QuitToMenu,
+    ToggleTieRule,
+    ToggleAimMode,
+    /// rounds of a versus match or a duel
+    CycleBestOf,
+    /// switch the player whose bindings are shown
+    CyclePlayer,
+    /// wait for the next button pressed and bind it to the action
+    Rebind(Action),
+    ResetBindings,
+    Back,
+}
+
+impl MenuScreen {
+    const fn title(self) -> &'static str {
+        match self {
+            Self::Closed => "",
+            Self::Main => "Rock Paper Scissors",
+            Self::Modes => "Modes",
+            Self::Settings => "Settings",
+            Self::Controls => "Controls",
+            Self::Pause => "Paused",
+        }
+    }
+
+    const fn items(self) -> &'static [(&'static str, MenuAction)] {
+        match self {
+            Self::Closed => &[],
+            Self::Main => &[
+                ("Play", MenuAction::Play),
+                ("Modes", MenuAction::Modes),
+                ("Settings", MenuAction::Settings),
+                ("Quit", MenuAction::Quit),
+            ],
+            Self::Modes => &[
+                ("Arcade", MenuAction::PlayMode(GameMode::Arcade)),
+                ("Co-op", MenuAction::PlayMode(GameMode::Coop)),
+                ("Versus", MenuAction::PlayMode(GameMode::Versus)),
+                ("Duel", MenuAction::PlayMode(GameMode::Duel)),
+                ("Duel vs CPU", MenuAction::PlayMode(GameMode::DuelCpu)),
+                ("Back", MenuAction::Back),
+            ],
+            Self::Settings => &[
+                ("Ties", MenuAction::ToggleTieRule),
+                ("Aim", MenuAction::ToggleAimMode),
+                ("Rounds", MenuAction::CycleBestOf),
+                ("Controls", MenuAction::Controls),
+                ("Back", MenuAction::Back),
+            ],
+            Self::Controls => &[
+                ("Player", MenuAction::CyclePlayer),
+                ("Up", MenuAction::Rebind(Action::MoveUp)),
+                ("Down", MenuAction::Rebind(Action::MoveDown)),
+                ("Left", MenuAction::Rebind(Action::MoveLeft)),
+                ("Right", MenuAction::Rebind(Action::MoveRight)),
+                ("Fire", MenuAction::Rebind(Action::Fire)),
+                ("Cycle hand", MenuAction::Rebind(Action::CycleHand)),
+                ("Pause", MenuAction::Rebind(Action::Pause)),
+                ("Debug", MenuAction::Rebind(Action::ToggleDebug)),
+                ("Inspector", MenuAction::Rebind(Action::ToggleInspector)),
+                ("Reset to defaults", MenuAction::ResetBindings),
+                ("Back", MenuAction::Back),
+            ],
+            Self::Pause => &[
+                ("Resume", MenuAction::Resume),
+                ("Restart", MenuAction::Restart),
+                ("Settings", MenuAction::Settings),
+                ("Quit to menu", MenuAction::QuitToMenu),
+            ],
+        }
+    }
+
+    /// screen `Back` leads to, from the pause menu when a run is paused
+    fn parent(self, game_state: GameState) -> Option<Self> {
+        match self {
+            Self::Modes | Self::Settings if game_state == GameState::Paused => Some(Self::Pause),
+            Self::Modes | Self::Settings => Some(Self::Main),
+            Self::Controls => Some(Self::Settings),
+            Self::Closed | Self::Main | Self::Pause => None,
+        }
+    }
+}
+
+/// Entry of the current menu, `index` is its rank from the top
+#[derive(Component, Debug)]
+struct MenuButton {
+    index: usize,
+}
+
+/// Entries of the current menu the mouse moved over or clicked this frame, by rank from the top
+///
+/// Recorded in replays along with the actions, so menus do not depend on the UI layout.
+#[derive(Resource, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MenuPointer {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hovered: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pressed: Option<usize>,
+}
+
+/// Text of a menu entry, some show a setting next to their label
+#[derive(Component, Debug)]
+struct EntryLabel {
+    label: &'static str,
+    action: MenuAction,
+}
+
+/// Rank of the focused entry of the current menu
+#[derive(Resource, Debug, Default)]
+struct MenuFocus(usize);
+
+/// Action waiting for a button to be bound to it
+#[derive(Resource, Debug, Default)]
+struct Rebinding(Option<Action>);
+
+/// Player whose bindings the controls menu shows and changes
+#[derive(Resource, Debug, Default)]
+struct ControlsPlayer(PlayerIndex);
+
+fn spawn_menu(
+    screen: Res<State<MenuScreen>>,
+    mut focus: ResMut<MenuFocus>,
+    mut commands: Commands,
+) {
+    let screen = *screen.get();
+    if screen == MenuScreen::Closed {
+        return;
+    }
+    focus.0 = 0;
+    commands
+        .spawn((
+            Name::new("Menu"),
+            StateScoped(screen),
+            NodeBundle {
+                background_color: BackgroundColor(Color::BLACK.with_alpha(0.5)),
+                z_index: ZIndex::Global(i32::MAX - 1),
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                screen.title(),
+                TextStyle {
+                    font_size: 64.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            for (index, (label, action)) in screen.items().iter().enumerate() {
+                parent
+                    .spawn((
+                        MenuButton { index },
+                        ButtonBundle {
+                            background_color: BackgroundColor(BUTTON_COLOR),
+                            style: Style {
+                                min_width: Val::Px(280.0),
+                                padding: UiRect::all(Val::Px(8.0)),
+                                justify_content: JustifyContent::Center,
+                                ..default()
+                            },
+                            ..default()
+                        },
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            EntryLabel {
+                                label,
+                                action: *action,
+                            },
+                            TextBundle::from_section(
+                                *label,
+                                TextStyle {
+                                    font_size: 28.0,
+                                    color: Color::WHITE,
+                                    ..default()
+                                },
+                            ),
+                        ));
+                    });
+            }
+        });
+}
+
+/// Inputs of a menu for one frame
+#[derive(Debug, Default)]
+#[allow(clippy::struct_excessive_bools)]
+struct MenuInput {
+    up: bool,
+    down: bool,
+    confirm: bool,
+    back: bool,
+    pause: bool,
+}
+
+impl MenuInput {
+    fn read(actions: &ButtonInput<Action>) -> Self {
+        Self {
+            up: actions.just_pressed(Action::MoveUp),
+            down: actions.just_pressed(Action::MoveDown),
+            confirm: actions.just_pressed(Action::Confirm),
+            back: actions.just_pressed(Action::Back),
+            pause: actions.just_pressed(Action::Pause),
+        }
+    }
+}
+
+fn open_pause(actions: Res<ButtonInput<Action>>, mut next_state: ResMut<NextState<GameState>>) {
+    if actions.just_pressed(Action::Pause) {
+        next_state.set(GameState::Paused);
+    }
+}
+
+fn read_menu_pointer(
+    entries: Query<(&MenuButton, &Interaction), Changed<Interaction>>,
+    mut pointer: ResMut<MenuPointer>,
+) {
+    let mut current = MenuPointer::default();
+    for (entry, interaction) in &entries {
+        match interaction {
+            Interaction::Hovered => current.hovered = Some(entry.index),
+            Interaction::Pressed => current.pressed = Some(entry.index),
+            Interaction::None => {}
+        }
+    }
+    pointer.set_if_neq(current);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn use_menu(
+    actions: Res<ButtonInput<Action>>,
+    pointer: Res<MenuPointer>,
+    screen: Res<State<MenuScreen>>,
+    game_state: Res<State<GameState>>,
+    mut focus: ResMut<MenuFocus>,
+    mut rebinding: ResMut<Rebinding>,
+    mut controls_player: ResMut<ControlsPlayer>,
+    mut game_mode: ResMut<GameMode>,
+    mut tie_rule: ResMut<TieRule>,
+    mut aim_mode: ResMut<AimMode>,
+    mut current_match: ResMut<Match>,
+    mut bindings: ResMut<Bindings>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let count = screen.items().len();
+    if count == 0 || rebinding.0.is_some() {
+        return;
+    }
+    let input = MenuInput::read(&actions);
+    if input.up {
+        focus.0 = (focus.0 + count - 1) % count;
+    }
+    if input.down {
+        focus.0 = (focus.0 + 1) % count;
+    }
+
+    if let Some(hovered) = pointer.hovered.filter(|index| *index < count) {
+        focus.0 = hovered;
+    }
+    let entry = |index: usize| screen.items().get(index).map(|(_, action)| *action);
+    let mut action = if input.confirm {
+        entry(focus.0)
+    } else {
+        pointer.pressed.and_then(entry)
+    };
+    if *screen.get() == MenuScreen::Pause && input.pause {
+        action = Some(MenuAction::Resume);
+    } else if input.back {
+        action = Some(MenuAction::Back);
+    }
+
+    let Some(action) = action else {
+        return;
+    };
+    match action {
+        MenuAction::Play | MenuAction::Resume => next_state.set(GameState::Playing),
+        MenuAction::PlayMode(mode) => {
+            *game_mode = mode;
+            next_state.set(GameState::Playing);
+        }
+        MenuAction::Modes => next_screen.set(MenuScreen::Modes),
+        MenuAction::Settings => next_screen.set(MenuScreen::Settings),
+        MenuAction::Controls => next_screen.set(MenuScreen::Controls),
+        MenuAction::Quit => {
+            exit.send(AppExit::Success);
+        }
+        MenuAction::Restart => next_state.set(GameState::Restarting),
+        MenuAction::QuitToMenu => next_state.set(GameState::MainMenu),
+        MenuAction::ToggleTieRule => {
+            *tie_rule = match *tie_rule {
+                TieRule::Bounce => TieRule::Annihilate,
+                TieRule::Annihilate => TieRule::Bounce,
+            };
+        }
+        MenuAction::ToggleAimMode => {
+            *aim_mode = match *aim_mode {
+                AimMode::Fixed => AimMode::Pointer,
+                AimMode::Pointer => AimMode::Fixed,
+            };
+        }
+        MenuAction::CycleBestOf => {
+            let next = BEST_OF_CHOICES
+                .iter()
+                .position(|best_of| *best_of == current_match.best_of)
+                .map_or(0, |index| (index + 1) % BEST_OF_CHOICES.len());
+            current_match.best_of = BEST_OF_CHOICES[next];
+        }
+        MenuAction::CyclePlayer => {
+            controls_player.0 = PlayerIndex((controls_player.0 .0 + 1) % MAX_PLAYERS);
+        }
+        MenuAction::Rebind(action) => rebinding.0 = Some(action),
+        MenuAction::ResetBindings => *bindings = Bindings::default(),
+        MenuAction::Back => {
+            if let Some(parent) = screen.parent(*game_state.get()) {
+                next_screen.set(parent);
+            }
+        }
+    }
+}
+
+/// Binds the first key or gamepad button pressed after choosing an action to rebind, Escape or
+/// `Back` cancels instead
+fn capture_binding(
+    actions: Res<ButtonInput<Action>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    pad_buttons: Res<ButtonInput<GamepadButton>>,
+    controls_player: Res<ControlsPlayer>,
+    mut rebinding: ResMut<Rebinding>,
+    mut bindings: ResMut<Bindings>,
+) {
+    // the button that started the rebinding is still down on this frame
+    if rebinding.is_changed() {
+        return;
+    }
+    let Some(action) = rebinding.0 else {
+        return;
+    };
+    if keys.just_pressed(KeyCode::Escape) || actions.just_pressed(Action::Back) {
+        rebinding.0 = None;
+        return;
+    }
+    let pressed = keys
+        .get_just_pressed()
+        .next()
+        .map(|key| Binding::Key(*key))
+        .or_else(|| {
+            pad_buttons
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Pad(button.button_type))
+        });
+    if let Some(binding) = pressed {
+        bindings.rebind(controls_player.0, action, binding);
+        rebinding.0 = None;
+    }
+}
+
+fn highlight_focus(focus: Res<MenuFocus>, mut entries: Query<(&MenuButton, &mut BackgroundColor)>) {
+    for (entry, mut color) in &mut entries {
+        color.0 = if entry.index == focus.0 {
+            FOCUSED_BUTTON_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+    }
+}
+
+fn refresh_labels(
+    tie_rule: Res<TieRule>,
+    aim_mode: Res<AimMode>,
+    current_match: Res<Match>,
+    bindings: Res<Bindings>,
+    rebinding: Res<Rebinding>,
+    controls_player: Res<ControlsPlayer>,
+    mut labels: Query<(Ref<EntryLabel>, &mut Text)>,
+) {
+    let changed = tie_rule.is_changed()
+        || aim_mode.is_changed()
+        || current_match.is_changed()
+        || bindings.is_changed()
+        || rebinding.is_changed()
+        || controls_player.is_changed();
+    let player = controls_player.0;
+    for (entry, mut text) in &mut labels {
+        if !changed && !entry.is_added() {
+            continue;
+        }
+        text.sections[0].value = match entry.action {
+            MenuAction::ToggleTieRule => format!("{}: {:?}", entry.label, *tie_rule),
+            MenuAction::ToggleAimMode => format!("{}: {:?}", entry.label, *aim_mode),
+            MenuAction::CycleBestOf => {
+                format!("{}: best of {}", entry.label, current_match.best_of)
+            }
+            MenuAction::CyclePlayer => format!("{}: P{}", entry.label, player.0 + 1),
+            MenuAction::Rebind(action) if rebinding.0 == Some(action) => {
+                format!("{}: press a button", entry.label)
+            }
+            MenuAction::Rebind(action) => {
+                format!("{}: {}", entry.label, bindings.describe(player, action))
+            }
+            _ => entry.label.to_string(),
+        };
+    }
+}
+
+fn open_main_menu(mut next_screen: ResMut<NextState<MenuScreen>>) {
+    next_screen.set(MenuScreen::Main);
+}
+
+fn open_pause_menu(mut next_screen: ResMut<NextState<MenuScreen>>) {
+    next_screen.set(MenuScreen::Pause);
+}
+
+fn close_menu(mut next_screen: ResMut<NextState<MenuScreen>>, mut rebinding: ResMut<Rebinding>) {
+    next_screen.set(MenuScreen::Closed);
+    rebinding.0 = None;
+}
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<MenuScreen>()
+            .enable_state_scoped_entities::<MenuScreen>()
+            .init_resource::<MenuFocus>()
+            .init_resource::<Rebinding>()
+            .init_resource::<ControlsPlayer>()
+            .init_resource::<MenuPointer>()
+            .add_systems(
+                PreUpdate,
+                read_menu_pointer
+                    .in_set(ActionSystems::Read)
+                    .after(UiSystem::Focus),
+            )
+            .add_systems(OnExit(GameState::MainMenu), close_menu)
+            .add_systems(OnExit(GameState::Paused), close_menu)
+            // after closing, going from pause back to the main menu reopens it
+            .add_systems(OnEnter(GameState::MainMenu), open_main_menu)
+            .add_systems(OnEnter(GameState::Paused), open_pause_menu);
+        for screen in [
+            MenuScreen::Main,
+            MenuScreen::Modes,
+            MenuScreen::Settings,
+            MenuScreen::Controls,
+            MenuScreen::Pause,
+        ] {
+            app.add_systems(OnEnter(screen), spawn_menu);
+        }
+        app.add_systems(
+            Update,
+            (
+                open_pause.run_if(in_state(GameState::Playing)),
+                (use_menu, capture_binding, highlight_focus, refresh_labels)
+                    .chain()
+                    .run_if(not(in_state(MenuScreen::Closed))),
+            ),
+        );
+    }
+}
@@ -1,14 +1,17 @@
 use bevy::{
-    app::{App, Update},
+    app::{App, FixedUpdate},
     math::Vec3,
-    prelude::{Component, Plugin, Query, Res, Transform},
+    prelude::{Component, Deref, DerefMut, IntoSystemConfigs, Plugin, Query, Res, Transform},
     reflect::Reflect,
     time::Time,
 };
 
-#[derive(Component, Reflect)]
+use crate::simulation::SimulationSystems;
+
+#[derive(Component, Reflect, Deref, DerefMut)]
 pub struct Velocity(Vec3);
 impl Velocity {
+    #[must_use]
     pub const fn new(x: f32, y: f32, z: f32) -> Self {
         Self(Vec3::new(x, y, z))
     }
@@ -31,6 +34,6 @@ fn move_things(time: Res<Time>, mut query: Query<(&Velocity, &mut Transform)>) {
 impl Plugin for MovementPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Velocity>()
-            .add_systems(Update, move_things);
+            .add_systems(FixedUpdate, move_things.in_set(SimulationSystems::Move));
     }
 }
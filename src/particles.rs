@@ -7,6 +7,7 @@ pub struct HanabiThing {
 }
 
 impl HanabiThing {
+    #[must_use]
     pub fn effect(&self) -> ParticleEffect {
         ParticleEffect::new(self.boom.clone())
     }
@@ -0,0 +1,36 @@
+use bevy::{
+    app::{App, Plugin},
+    color::Color,
+    prelude::Component,
+    reflect::Reflect,
+};
+
+/// local players that can play at the same time
+pub const MAX_PLAYERS: usize = 2;
+
+/// color of each player's cannon and HUD
+pub const PLAYER_COLORS: [Color; MAX_PLAYERS] =
+    [Color::srgb(0.8, 0.5, 0.5), Color::srgb(0.5, 0.6, 0.9)];
+
+/// Local player controlling a cannon, from 0
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PlayerIndex(pub usize);
+
+impl PlayerIndex {
+    #[must_use]
+    pub const fn color(self) -> Color {
+        PLAYER_COLORS[self.0 % MAX_PLAYERS]
+    }
+}
+
+/// Player whose cannon fired a hand, `None` for hands fired by enemies
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Owner(pub Option<PlayerIndex>);
+
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<PlayerIndex>().register_type::<Owner>();
+    }
+}
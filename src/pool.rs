@@ -0,0 +1,98 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic},
+    ecs::world::Command,
+    math::Vec3,
+    prelude::{
+        Bundle, Commands, Component, Entity, OnExit, Query, ResMut, Resource, Visibility, With,
+        World,
+    },
+    reflect::Reflect,
+};
+
+use crate::{movement::Velocity, state::InGame};
+
+/// Entity that goes back to the [`HandPool`] instead of being despawned when [retired](Retire)
+#[derive(Component, Reflect, Debug)]
+pub struct Pooled;
+
+/// Pooled entity waiting in the [`HandPool`] to be reused
+///
+/// Dormant entities are hidden and still, gameplay systems should ignore them.
+#[derive(Component, Reflect, Debug)]
+pub struct Dormant;
+
+/// Fired hands that left play and can be reused by the next shot
+#[derive(Resource, Debug, Default)]
+pub struct HandPool {
+    free: Vec<Entity>,
+}
+
+impl HandPool {
+    /// wakes up a dormant hand with `bundle`, or spawns a new one if the pool is empty
+    pub fn spawn(&mut self, commands: &mut Commands, bundle: impl Bundle) -> Entity {
+        if let Some(entity) = self.free.pop() {
+            commands.entity(entity).insert(bundle).remove::<Dormant>();
+            entity
+        } else {
+            commands.spawn((bundle, Pooled)).id()
+        }
+    }
+}
+
+/// Removes an entity from play: despawns it, or puts it to sleep in the [`HandPool`] if it is [`Pooled`]
+///
+/// Everything that ends the life of a hand should go through this, so pooled hands are never lost.
+pub struct Retire(pub Entity);
+
+impl Command for Retire {
+    fn apply(self, world: &mut World) {
+        let Some(mut entity) = world.get_entity_mut(self.0) else {
+            return;
+        };
+        if !entity.contains::<Pooled>() {
+            entity.despawn();
+            return;
+        }
+        // retired twice on the same frame
+        if entity.contains::<Dormant>() {
+            return;
+        }
+        entity.insert((Dormant, Visibility::Hidden));
+        if let Some(mut velocity) = entity.get_mut::<Velocity>() {
+            **velocity = Vec3::ZERO;
+        }
+        world.resource_mut::<HandPool>().free.push(self.0);
+    }
+}
+
+/// pooled entities are scoped to the run, they are gone once it ends
+fn empty_pool(mut pool: ResMut<HandPool>) {
+    pool.free.clear();
+}
+
+pub const POOL_SIZE: DiagnosticPath = DiagnosticPath::const_new("hand_pool/size");
+pub const POOL_DORMANT: DiagnosticPath = DiagnosticPath::const_new("hand_pool/dormant");
+
+fn pool_diagnostics(
+    mut diagnostics: Diagnostics,
+    pooled: Query<(), With<Pooled>>,
+    dormant: Query<(), With<Dormant>>,
+) {
+    diagnostics.add_measurement(&POOL_SIZE, || pooled.iter().count() as f64);
+    diagnostics.add_measurement(&POOL_DORMANT, || dormant.iter().count() as f64);
+}
+
+pub struct PoolPlugin;
+
+impl Plugin for PoolPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Pooled>()
+            .register_type::<Dormant>()
+            .init_resource::<HandPool>()
+            .register_diagnostic(Diagnostic::new(POOL_SIZE))
+            .register_diagnostic(Diagnostic::new(POOL_DORMANT))
+            .add_systems(OnExit(InGame), empty_pool)
+            .add_systems(Update, pool_diagnostics);
+    }
+}
@@ -0,0 +1,333 @@
+use std::{collections::VecDeque, fmt, path::PathBuf, time::Duration};
+
+use bevy::{
+    app::{App, AppExit, First, Last, Plugin, PreUpdate},
+    input::ButtonInput,
+    log::{error, info},
+    prelude::{
+        resource_exists, Commands, DetectChangesMut, EventReader, EventWriter, IntoSystemConfigs,
+        Res, ResMut, Resource,
+    },
+    time::{Fixed, Real, Time, TimeSystem, TimeUpdateStrategy},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::{Action, ActionSystems, PlayerActions},
+    aim::PointerInput,
+    config::LaunchOptions,
+    menu::MenuPointer,
+    player::PlayerIndex,
+    rng::GameRng,
+};
+
+/// Actions, pointer and frame duration that changed on one frame
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ReplayFrame {
+    /// frames since startup
+    pub frame: u32,
+    /// real time since the previous frame, only stored when it changed, frames in between last
+    /// as long as the last one stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta: Option<Duration>,
+    /// player index and action
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pressed: Vec<(usize, Action)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub released: Vec<(usize, Action)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pointer: Option<PointerInput>,
+    /// menu entries hovered or clicked on this frame only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub menu: Option<MenuPointer>,
+}
+
+/// Everything needed to play a session again, as written in `*.replay.ron` files
+///
+/// Actions are stored rather than keys, so replays do not depend on the bindings.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Replay {
+    pub seed: u64,
+    /// simulation ticks per second
+    pub tick_rate: f64,
+    /// asset path of the hand ruleset, the default one if `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hand_rules: Option<String>,
+    pub frames: Vec<ReplayFrame>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Write(ron::Error),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not access replay file: {err}"),
+            Self::Parse(err) => write!(f, "could not parse replay: {err}"),
+            Self::Write(err) => write!(f, "could not serialize replay: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for ReplayError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl From<ron::Error> for ReplayError {
+    fn from(err: ron::Error) -> Self {
+        Self::Write(err)
+    }
+}
+
+impl Replay {
+    /// # Errors
+    /// if the file can't be read or is not a valid replay
+    pub fn load(path: &PathBuf) -> Result<Self, ReplayError> {
+        Ok(ron::de::from_bytes(&std::fs::read(path)?)?)
+    }
+
+    /// # Errors
+    /// if the file can't be written
+    pub fn save(&self, path: &PathBuf) -> Result<(), ReplayError> {
+        Ok(std::fs::write(path, ron::ser::to_string(self)?)?)
+    }
+}
+
+#[derive(Resource, Debug)]
+struct ReplayRecorder {
+    path: PathBuf,
+    hand_rules: Option<String>,
+    frames: Vec<ReplayFrame>,
+    frame: u32,
+    pointer: PointerInput,
+    delta: Duration,
+}
+
+#[derive(Resource, Debug)]
+struct ReplayPlayback {
+    frames: VecDeque<ReplayFrame>,
+    frame: u32,
+    /// actions in the replay, replace the live ones every frame
+    actions: PlayerActions,
+    pointer: PointerInput,
+    /// quit once the replay is over, there is no live input to go back to when headless
+    exit_when_over: bool,
+}
+
+fn record_inputs(
+    time: Res<Time<Real>>,
+    players: Res<PlayerActions>,
+    pointer: Res<PointerInput>,
+    menu: Res<MenuPointer>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    let changes = |changed: fn(&ButtonInput<Action>) -> Vec<Action>| {
+        players
+            .iter()
+            .flat_map(|(player, actions)| {
+                changed(actions)
+                    .into_iter()
+                    .map(move |action| (player.0, action))
+            })
+            .collect()
+    };
+    let frame = ReplayFrame {
+        frame: recorder.frame,
+        delta: (time.delta() != recorder.delta).then_some(time.delta()),
+        pressed: changes(|actions| actions.get_just_pressed().copied().collect()),
+        released: changes(|actions| actions.get_just_released().copied().collect()),
+        pointer: (*pointer != recorder.pointer).then_some(*pointer),
+        menu: (*menu != MenuPointer::default()).then_some(*menu),
+    };
+    recorder.frame += 1;
+    recorder.pointer = *pointer;
+    recorder.delta = time.delta();
+    if !(frame.pressed.is_empty()
+        && frame.released.is_empty()
+        && frame.pointer.is_none()
+        && frame.menu.is_none()
+        && frame.delta.is_none())
+    {
+        recorder.frames.push(frame);
+    }
+}
+
+fn save_recording(
+    mut exit: EventReader<AppExit>,
+    recorder: Res<ReplayRecorder>,
+    rng: Res<GameRng>,
+    fixed_time: Res<Time<Fixed>>,
+) {
+    if exit.read().last().is_none() {
+        return;
+    }
+    let replay = Replay {
+        seed: rng.seed(),
+        tick_rate: 1.0 / fixed_time.timestep().as_secs_f64(),
+        hand_rules: recorder.hand_rules.clone(),
+        // an empty last frame, so the replay lasts as long as the session
+        frames: [
+            recorder.frames.clone(),
+            vec![ReplayFrame {
+                frame: recorder.frame,
+                ..ReplayFrame::default()
+            }],
+        ]
+        .concat(),
+    };
+    match replay.save(&recorder.path) {
+        Ok(()) => info!("replay saved to {}", recorder.path.display()),
+        Err(err) => error!("{err}"),
+    }
+}
+
+/// Frames last as long as they did when recorded, time is updated right after this
+fn play_back_time(playback: Res<ReplayPlayback>, mut strategy: ResMut<TimeUpdateStrategy>) {
+    // the duration set last carries on until a frame stores another one
+    if let Some(delta) = playback
+        .frames
+        .front()
+        .filter(|frame| frame.frame == playback.frame)
+        .and_then(|frame| frame.delta)
+    {
+        *strategy = TimeUpdateStrategy::ManualDuration(delta);
+    }
+}
+
+fn play_back_inputs(
+    mut players: ResMut<PlayerActions>,
+    mut pointer: ResMut<PointerInput>,
+    mut menu: ResMut<MenuPointer>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut exit: EventWriter<AppExit>,
+    mut commands: Commands,
+) {
+    let playback = &mut *playback;
+    playback.actions.clear();
+    menu.set_if_neq(MenuPointer::default());
+    let now = playback.frame;
+    while playback
+        .frames
+        .front()
+        .is_some_and(|frame| frame.frame <= now)
+    {
+        let Some(frame) = playback.frames.pop_front() else {
+            break;
+        };
+        for (player, action) in frame.pressed {
+            if let Some(actions) = playback.actions.get_mut(PlayerIndex(player)) {
+                actions.press(action);
+            }
+        }
+        for (player, action) in frame.released {
+            if let Some(actions) = playback.actions.get_mut(PlayerIndex(player)) {
+                actions.release(action);
+            }
+        }
+        if let Some(recorded) = frame.pointer {
+            playback.pointer = recorded;
+        }
+        if let Some(recorded) = frame.menu {
+            *menu = recorded;
+        }
+    }
+    playback.frame += 1;
+    *players = playback.actions.clone();
+    *pointer = playback.pointer;
+    if !playback.frames.is_empty() {
+        return;
+    }
+    if playback.exit_when_over {
+        info!("replay over");
+        exit.send(AppExit::Success);
+    } else {
+        info!("replay over, back to live input");
+        commands.remove_resource::<ReplayPlayback>();
+        commands.insert_resource(TimeUpdateStrategy::Automatic);
+    }
+}
+
+/// Records player actions to `--record <file>`, or plays `--replay <file>` back instead of it
+///
+/// Replays play every frame back with the duration it was recorded with, so the same inputs lead
+/// to the same game.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let mut options = app
+            .world()
+            .get_resource::<LaunchOptions>()
+            .cloned()
+            .unwrap_or_default();
+
+        if let Some(path) = &options.replay {
+            match Replay::load(path) {
+                Ok(replay) => {
+                    info!("playing back {}", path.display());
+                    options = LaunchOptions {
+                        seed: Some(replay.seed),
+                        tick_rate: Some(replay.tick_rate),
+                        hand_rules: replay.hand_rules,
+                        ..options
+                    };
+                    // recorded deltas start from the first frame, which always lasts 0
+                    app.insert_resource(options.clone())
+                        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO))
+                        .insert_resource(ReplayPlayback {
+                            frames: replay.frames.into(),
+                            frame: 0,
+                            actions: PlayerActions::default(),
+                            pointer: PointerInput::default(),
+                            exit_when_over: options.headless,
+                        });
+                }
+                Err(err) => error!("{}: {err}", path.display()),
+            }
+        }
+        if let Some(path) = options.record {
+            app.insert_resource(ReplayRecorder {
+                path,
+                hand_rules: options.hand_rules,
+                frames: Vec::new(),
+                frame: 0,
+                pointer: PointerInput::default(),
+                delta: Duration::ZERO,
+            });
+        }
+        app.add_systems(
+            First,
+            play_back_time
+                .run_if(resource_exists::<ReplayPlayback>)
+                .before(TimeSystem),
+        )
+        .add_systems(
+            PreUpdate,
+            (
+                play_back_inputs.run_if(resource_exists::<ReplayPlayback>),
+                record_inputs.run_if(resource_exists::<ReplayRecorder>),
+            )
+                .chain()
+                .after(ActionSystems::Read)
+                .before(ActionSystems::Merge),
+        )
+        .add_systems(
+            Last,
+            save_recording.run_if(resource_exists::<ReplayRecorder>),
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use bevy::{
+    app::{App, Plugin},
+    log::info,
+    prelude::{FromWorld, OnEnter, OnExit, ResMut, Resource, World},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{config::LaunchOptions, state::GameState};
+
+/// Independent random sequences, one per system drawing random numbers
+///
+/// Systems each get their own stream so that adding a random call in one of them does not
+/// shift the numbers drawn by the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RngStream {
+    /// hands picked by the wave spawner
+    Waves,
+    /// patterns of the player cannon
+    PlayerFire,
+    /// patterns of enemy emitters
+    EnemyFire,
+    /// spread of every weapon
+    Spread,
+    /// hands picked by the computer in a duel
+    Duel,
+}
+
+impl RngStream {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Waves => "waves",
+            Self::PlayerFire => "player fire",
+            Self::EnemyFire => "enemy fire",
+            Self::Spread => "spread",
+            Self::Duel => "duel",
+        }
+    }
+}
+
+/// Source of every random number of the game, so runs can be reproduced from their seed
+///
+/// Particle effects are left out: they are drawn on the GPU and do not affect gameplay.
+#[derive(Resource, Debug)]
+pub struct GameRng {
+    seed: u64,
+    streams: HashMap<RngStream, StdRng>,
+}
+
+impl GameRng {
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            streams: HashMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn stream(&mut self, stream: RngStream) -> &mut StdRng {
+        let seed = self.seed;
+        self.streams.entry(stream).or_insert_with(|| {
+            let mut hasher = blake3::Hasher::new_derive_key("RPSGame game rng stream");
+            hasher.update(&seed.to_le_bytes());
+            hasher.update(stream.name().as_bytes());
+            StdRng::from_seed(*hasher.finalize().as_bytes())
+        })
+    }
+
+    /// starts every stream over, the next run draws the same numbers as the previous one
+    ///
+    /// Rounds of a match keep drawing from the same streams, so they all play out differently.
+    fn restart(&mut self) {
+        self.streams.clear();
+    }
+}
+
+impl FromWorld for GameRng {
+    fn from_world(world: &mut World) -> Self {
+        let seed = world
+            .get_resource::<LaunchOptions>()
+            .and_then(|options| options.seed)
+            .unwrap_or_else(rand::random);
+        info!("rng seed: {seed}");
+        Self::new(seed)
+    }
+}
+
+fn restart_rng(mut rng: ResMut<GameRng>) {
+    rng.restart();
+}
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        // every way into a run except from the previous round of a match
+        app.add_systems(OnExit(GameState::MainMenu), restart_rng)
+            .add_systems(OnExit(GameState::GameOver), restart_rng)
+            .add_systems(OnEnter(GameState::Restarting), restart_rng);
+    }
+
+    fn finish(&self, app: &mut App) {
+        // after every plugin is built, so they all had a chance to pick the seed
+        app.init_resource::<GameRng>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+
+    fn draw(rng: &mut GameRng, stream: RngStream) -> Vec<u64> {
+        (0..8).map(|_| rng.stream(stream).gen()).collect()
+    }
+
+    #[test]
+    fn same_seed_draws_the_same_numbers() {
+        let mut rng = GameRng::new(42);
+        let mut other = GameRng::new(42);
+        assert_eq!(
+            draw(&mut rng, RngStream::Waves),
+            draw(&mut other, RngStream::Waves)
+        );
+        assert_ne!(
+            draw(&mut GameRng::new(42), RngStream::Waves),
+            draw(&mut GameRng::new(43), RngStream::Waves)
+        );
+    }
+
+    #[test]
+    fn streams_are_independent() {
+        let mut rng = GameRng::new(42);
+        let mut other = GameRng::new(42);
+        // drawing from one stream does not shift the others
+        draw(&mut other, RngStream::Spread);
+        assert_eq!(
+            draw(&mut rng, RngStream::Waves),
+            draw(&mut other, RngStream::Waves)
+        );
+        assert_ne!(
+            draw(&mut GameRng::new(42), RngStream::Waves),
+            draw(&mut GameRng::new(42), RngStream::Spread)
+        );
+    }
+
+    #[test]
+    fn restart_starts_every_stream_over() {
+        let mut rng = GameRng::new(42);
+        let waves = draw(&mut rng, RngStream::Waves);
+        let duel = draw(&mut rng, RngStream::Duel);
+        rng.restart();
+        assert_eq!(draw(&mut rng, RngStream::Waves), waves);
+        assert_eq!(draw(&mut rng, RngStream::Duel), duel);
+        assert_eq!(rng.seed(), 42);
+    }
+}
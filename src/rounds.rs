@@ -0,0 +1,267 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    prelude::{
+        in_state, resource_equals, Commands, Event, EventReader, EventWriter, IntoSystemConfigs,
+        NextState, OnEnter, OnExit, Query, Res, ResMut, Resource, With,
+    },
+    reflect::Reflect,
+    time::{Time, Timer, TimerMode},
+};
+
+use crate::{
+    hand_cannon::HandCannon,
+    health::Hit,
+    player::{PlayerIndex, MAX_PLAYERS},
+    state::{in_match, spawn_banner, GameMode, GameState},
+};
+
+/// rounds in a match unless changed from the settings
+const DEFAULT_BEST_OF: u32 = 5;
+/// choices offered by the settings menu
+pub const BEST_OF_CHOICES: [u32; 3] = [3, 5, 7];
+/// seconds the result of a round stays on screen before the next one starts
+const ROUND_PAUSE: f32 = 2.0;
+
+/// Rounds won by each player in the current match, see [`GameMode::is_match`]
+#[derive(Resource, Reflect, Debug, Clone)]
+pub struct Match {
+    /// the match is over once a player won more than half of this many rounds
+    pub best_of: u32,
+    pub wins: [u32; MAX_PLAYERS],
+    /// winner of the last round, `None` before the first one or after a draw
+    pub last_winner: Option<PlayerIndex>,
+}
+
+impl Default for Match {
+    fn default() -> Self {
+        Self {
+            best_of: DEFAULT_BEST_OF,
+            wins: [0; MAX_PLAYERS],
+            last_winner: None,
+        }
+    }
+}
+
+impl Match {
+    #[must_use]
+    pub const fn rounds_to_win(&self) -> u32 {
+        self.best_of / 2 + 1
+    }
+
+    /// player who won enough rounds to take the match
+    pub fn winner(&self) -> Option<PlayerIndex> {
+        (0..MAX_PLAYERS)
+            .find(|player| self.wins[*player] >= self.rounds_to_win())
+            .map(PlayerIndex)
+    }
+
+    const fn record(&mut self, winner: Option<PlayerIndex>) {
+        if let Some(winner) = winner {
+            self.wins[winner.0] += 1;
+        }
+        self.last_winner = winner;
+    }
+
+    /// starts a new match with the same number of rounds
+    fn reset(&mut self) {
+        *self = Self {
+            best_of: self.best_of,
+            ..Self::default()
+        };
+    }
+
+    /// rounds won so far, as shown between rounds and on the HUD
+    #[must_use]
+    pub fn tally(&self, mode: GameMode) -> String {
+        format!(
+            "{} {} - {} {}",
+            mode.player_name(PlayerIndex(0)),
+            self.wins[0],
+            self.wins[1],
+            mode.player_name(PlayerIndex(1))
+        )
+    }
+}
+
+/// Sent when a round of a match is over, `winner` is `None` for a draw
+#[derive(Event, Debug, Clone, Copy)]
+pub struct RoundEnded {
+    pub winner: Option<PlayerIndex>,
+}
+
+/// In versus, a round is lost as soon as a hand gets through to the cannon
+fn end_versus_round_on_hit(
+    mut hits: EventReader<Hit>,
+    cannons: Query<&PlayerIndex, With<HandCannon>>,
+    mut ended: EventWriter<RoundEnded>,
+) {
+    let mut hit = [false; MAX_PLAYERS];
+    for player in hits.read().filter_map(|hit| cannons.get(hit.target).ok()) {
+        hit[player.0] = true;
+    }
+    let winner = match hit {
+        [false, false] => return,
+        // both cannons were hit on the same frame
+        [true, true] => None,
+        [first_hit, _] => Some(PlayerIndex(usize::from(first_hit))),
+    };
+    ended.send(RoundEnded { winner });
+}
+
+fn finish_round(
+    mut ended: EventReader<RoundEnded>,
+    mut current: ResMut<Match>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // a single round can only end once, whatever ends it last on the frame wins
+    let Some(winner) = ended.read().last().map(|round| round.winner) else {
+        return;
+    };
+    current.record(winner);
+    next_state.set(if current.winner().is_some() {
+        GameState::GameOver
+    } else {
+        GameState::RoundOver
+    });
+}
+
+/// Time left before the next round starts
+#[derive(Resource, Debug)]
+struct RoundPause(Timer);
+
+fn show_round_result(current: Res<Match>, mode: Res<GameMode>, mut commands: Commands) {
+    let title = current.last_winner.map_or_else(
+        || "Draw".to_string(),
+        |winner| format!("{} takes the round", mode.player_name(winner)),
+    );
+    spawn_banner(
+        &mut commands,
+        GameState::RoundOver,
+        &title,
+        &current.tally(*mode),
+    );
+    commands.insert_resource(RoundPause(Timer::from_seconds(
+        ROUND_PAUSE,
+        TimerMode::Once,
+    )));
+}
+
+fn start_next_round(
+    time: Res<Time>,
+    mut pause: ResMut<RoundPause>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if pause.0.tick(time.delta()).finished() {
+        next_state.set(GameState::Playing);
+    }
+}
+
+fn show_match_result(current: Res<Match>, mode: Res<GameMode>, mut commands: Commands) {
+    let title = current.winner().map_or_else(
+        || "Match over".to_string(),
+        |winner| format!("{} wins the match", mode.player_name(winner)),
+    );
+    spawn_banner(
+        &mut commands,
+        GameState::GameOver,
+        &title,
+        &format!(
+            "{}\npress Enter to play again, Escape for the menu",
+            current.tally(*mode)
+        ),
+    );
+}
+
+fn reset_match(mut current: ResMut<Match>) {
+    current.reset();
+}
+
+pub struct RoundsPlugin;
+
+impl Plugin for RoundsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Match>()
+            .init_resource::<Match>()
+            .add_event::<RoundEnded>()
+            // every way into a run except from the previous round starts a new match
+            .add_systems(OnExit(GameState::MainMenu), reset_match)
+            .add_systems(OnExit(GameState::GameOver), reset_match)
+            .add_systems(OnEnter(GameState::Restarting), reset_match)
+            .add_systems(OnEnter(GameState::RoundOver), show_round_result)
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                show_match_result.run_if(in_match),
+            )
+            .add_systems(
+                Update,
+                (
+                    (
+                        end_versus_round_on_hit.run_if(resource_equals(GameMode::Versus)),
+                        finish_round,
+                    )
+                        .chain()
+                        .run_if(in_state(GameState::Playing)),
+                    start_next_round.run_if(in_state(GameState::RoundOver)),
+                ),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn best_of(best_of: u32) -> Match {
+        Match {
+            best_of,
+            ..Match::default()
+        }
+    }
+
+    #[test]
+    fn more_than_half_of_the_rounds_win() {
+        for (rounds, to_win) in [(3, 2), (5, 3), (7, 4)] {
+            assert_eq!(best_of(rounds).rounds_to_win(), to_win, "best of {rounds}");
+        }
+    }
+
+    #[test]
+    fn match_is_won_once_enough_rounds_are() {
+        for rounds in BEST_OF_CHOICES {
+            let mut current = best_of(rounds);
+            for _ in 1..current.rounds_to_win() {
+                current.record(Some(PlayerIndex(1)));
+                assert_eq!(current.winner(), None, "best of {rounds}");
+            }
+            current.record(Some(PlayerIndex(1)));
+            assert_eq!(current.winner(), Some(PlayerIndex(1)), "best of {rounds}");
+        }
+    }
+
+    #[test]
+    fn records_wins_and_draws() {
+        let mut current = best_of(3);
+        current.record(Some(PlayerIndex(0)));
+        assert_eq!(current.wins, [1, 0]);
+        assert_eq!(current.last_winner, Some(PlayerIndex(0)));
+
+        // a draw counts for nobody
+        current.record(None);
+        assert_eq!(current.wins, [1, 0]);
+        assert_eq!(current.last_winner, None);
+
+        current.record(Some(PlayerIndex(1)));
+        assert_eq!(current.wins, [1, 1]);
+        assert_eq!(current.winner(), None);
+    }
+
+    #[test]
+    fn reset_keeps_the_number_of_rounds() {
+        let mut current = best_of(7);
+        current.record(Some(PlayerIndex(0)));
+        current.reset();
+        assert_eq!(current.best_of, 7);
+        assert_eq!(current.wins, [0, 0]);
+        assert_eq!(current.last_winner, None);
+    }
+}
@@ -0,0 +1,208 @@
+use bevy::{
+    app::{App, FixedUpdate, Plugin},
+    prelude::{Component, Entity, Event, EventReader, EventWriter, IntoSystemConfigs, Query, Res},
+    reflect::Reflect,
+    time::{Time, Timer, TimerMode},
+};
+use bevy_trauma_shake::TraumaEvent;
+
+use crate::{
+    combat::HandClash,
+    player::{Owner, PlayerIndex},
+    simulation::SimulationSystems,
+};
+
+/// points of a player win, before the multiplier
+const WIN_POINTS: f32 = 100.0;
+/// seconds a win keeps the combo going
+const COMBO_WINDOW: f32 = 1.5;
+/// consecutive wins needed for a combo
+const COMBO_MIN: u32 = 2;
+/// multiplier gained by every player win
+const MULTIPLIER_STEP: f32 = 0.1;
+const MAX_MULTIPLIER: f32 = 5.0;
+/// multiplier lost every second, down to 1
+const MULTIPLIER_DECAY: f32 = 0.25;
+/// screen shake of every win in a combo, for each hand of the combo
+const COMBO_TRAUMA: f32 = 0.02;
+const MAX_COMBO_TRAUMA: f32 = 0.4;
+
+/// How well a player is doing in the current run, on their cannon
+#[derive(Component, Reflect, Debug)]
+pub struct Score {
+    pub points: u64,
+    /// consecutive wins, each less than [`COMBO_WINDOW`] seconds after the previous one
+    pub combo: u32,
+    pub best_combo: u32,
+    /// applied to the points of every win, grows with wins and decays with time
+    pub multiplier: f32,
+    combo_timer: Timer,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Self {
+            points: 0,
+            combo: 0,
+            best_combo: 0,
+            multiplier: 1.0,
+            combo_timer: Timer::from_seconds(COMBO_WINDOW, TimerMode::Once),
+        }
+    }
+}
+
+impl Score {
+    #[must_use]
+    pub const fn in_combo(&self) -> bool {
+        self.combo >= COMBO_MIN
+    }
+
+    /// returns the number of wins in a row, including this one
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn win(&mut self) -> u32 {
+        self.combo += 1;
+        self.best_combo = self.best_combo.max(self.combo);
+        self.combo_timer.reset();
+        self.multiplier = (self.multiplier + MULTIPLIER_STEP).min(MAX_MULTIPLIER);
+        // the multiplier never goes below 1
+        self.points += (WIN_POINTS * self.multiplier).round() as u64;
+        self.combo
+    }
+
+    /// ends the current chain of wins, returns its length if it was a combo
+    fn break_combo(&mut self) -> Option<u32> {
+        let was_combo = self.in_combo();
+        let combo = std::mem::take(&mut self.combo);
+        was_combo.then_some(combo)
+    }
+}
+
+/// Sent when a player wins enough clashes in a row to start a combo
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ComboStarted {
+    pub player: PlayerIndex,
+    /// number of consecutive wins
+    pub combo: u32,
+    /// winning hand of the clash that started the combo
+    pub hand: Entity,
+}
+
+/// Sent for every win of a player after the one that started their combo
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ComboExtended {
+    pub player: PlayerIndex,
+    pub combo: u32,
+    pub hand: Entity,
+}
+
+/// Sent when a combo ends, because the window ran out or a hand of the player lost
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ComboBroken {
+    pub player: PlayerIndex,
+    pub combo: u32,
+}
+
+fn decay_scores(
+    time: Res<Time>,
+    mut scores: Query<(&PlayerIndex, &mut Score)>,
+    mut broken: EventWriter<ComboBroken>,
+) {
+    for (player, mut score) in &mut scores {
+        score.multiplier = MULTIPLIER_DECAY
+            .mul_add(-time.delta_seconds(), score.multiplier)
+            .max(1.0);
+        if score.combo == 0 {
+            continue;
+        }
+        score.combo_timer.tick(time.delta());
+        if score.combo_timer.finished() {
+            if let Some(combo) = score.break_combo() {
+                broken.send(ComboBroken {
+                    player: *player,
+                    combo,
+                });
+            }
+        }
+    }
+}
+
+/// Wins score for the player who fired the winning hand, losses break the combo of the player
+/// who fired the losing one
+fn score_clashes(
+    mut clashes: EventReader<HandClash>,
+    owners: Query<&Owner>,
+    mut scores: Query<(&PlayerIndex, &mut Score)>,
+    mut started: EventWriter<ComboStarted>,
+    mut extended: EventWriter<ComboExtended>,
+    mut broken: EventWriter<ComboBroken>,
+) {
+    for clash in clashes.read() {
+        let Some(winner) = clash.winner() else {
+            continue;
+        };
+        let loser = if winner == clash.first {
+            clash.second
+        } else {
+            clash.first
+        };
+        let owner = |hand| owners.get(hand).ok().and_then(|owner| owner.0);
+        // both hands may belong to players, e.g. in versus
+        if let Some((&player, mut score)) =
+            owner(winner).and_then(|player| scores.iter_mut().find(|(index, _)| **index == player))
+        {
+            let combo = score.win();
+            if combo == COMBO_MIN {
+                started.send(ComboStarted {
+                    player,
+                    combo,
+                    hand: winner,
+                });
+            } else if combo > COMBO_MIN {
+                extended.send(ComboExtended {
+                    player,
+                    combo,
+                    hand: winner,
+                });
+            }
+        }
+        if let Some((&player, mut score)) =
+            owner(loser).and_then(|player| scores.iter_mut().find(|(index, _)| **index == player))
+        {
+            if let Some(combo) = score.break_combo() {
+                broken.send(ComboBroken { player, combo });
+            }
+        }
+    }
+}
+
+/// Every win of a combo shakes the screen a bit more
+fn shake_combos(
+    mut started: EventReader<ComboStarted>,
+    mut extended: EventReader<ComboExtended>,
+    mut trauma: EventWriter<TraumaEvent>,
+) {
+    let combos = started
+        .read()
+        .map(|event| event.combo)
+        .chain(extended.read().map(|event| event.combo));
+    for combo in combos {
+        trauma.send((COMBO_TRAUMA * combo as f32).min(MAX_COMBO_TRAUMA).into());
+    }
+}
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Score>()
+            .add_event::<ComboStarted>()
+            .add_event::<ComboExtended>()
+            .add_event::<ComboBroken>()
+            .add_systems(
+                FixedUpdate,
+                (decay_scores, score_clashes, shake_combos)
+                    .chain()
+                    .in_set(SimulationSystems::React),
+            );
+    }
+}
@@ -0,0 +1,105 @@
+use bevy::{
+    app::{App, FixedFirst, FixedLast, FixedUpdate, Plugin, PostUpdate},
+    math::Vec3,
+    prelude::{
+        in_state, Component, IntoSystemConfigs, IntoSystemSetConfigs, Query, Res, SystemSet,
+        Transform,
+    },
+    time::{Fixed, Time},
+    transform::TransformSystem,
+};
+
+use crate::{config::LaunchOptions, state::GameState};
+
+/// simulation ticks per second when not given with `--tick-rate`
+pub const DEFAULT_TICK_RATE: f64 = 64.0;
+
+/// Steps of a simulation tick, they all run in [`FixedUpdate`] while playing
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SimulationSystems {
+    /// weapons fire and waves spawn
+    Spawn,
+    /// everything with a velocity moves
+    Move,
+    /// hands clash
+    Collide,
+    /// score and everything else following from the clashes
+    React,
+    /// expired and out of bounds entities go
+    Despawn,
+}
+
+/// Smooths the [`Transform`] of a simulated entity between two ticks
+///
+/// Simulation systems see the position of the last tick, everything after them sees a position
+/// interpolated between the last two ticks.
+#[derive(Component, Debug, Default)]
+pub struct Interpolated {
+    /// position at the start and the end of the last tick, none until the entity was simulated
+    positions: Option<(Vec3, Vec3)>,
+}
+
+fn restore_simulated_positions(mut query: Query<(&mut Interpolated, &mut Transform)>) {
+    for (mut interpolated, mut transform) in &mut query {
+        if let Some((previous, current)) = interpolated.positions.as_mut() {
+            transform.translation = *current;
+            *previous = *current;
+        }
+    }
+}
+
+fn store_simulated_positions(mut query: Query<(&mut Interpolated, &Transform)>) {
+    for (mut interpolated, transform) in &mut query {
+        let current = transform.translation;
+        let previous = interpolated
+            .positions
+            .map_or(current, |(previous, _)| previous);
+        interpolated.positions = Some((previous, current));
+    }
+}
+
+fn interpolate_positions(
+    time: Res<Time<Fixed>>,
+    mut query: Query<(&Interpolated, &mut Transform)>,
+) {
+    let progress = time.overstep_fraction();
+    for (interpolated, mut transform) in &mut query {
+        if let Some((previous, current)) = interpolated.positions {
+            transform.translation = previous.lerp(current, progress);
+        }
+    }
+}
+
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets(
+            FixedUpdate,
+            (
+                SimulationSystems::Spawn,
+                SimulationSystems::Move,
+                SimulationSystems::Collide,
+                SimulationSystems::React,
+                SimulationSystems::Despawn,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(FixedFirst, restore_simulated_positions)
+        .add_systems(FixedLast, store_simulated_positions)
+        .add_systems(
+            PostUpdate,
+            interpolate_positions.before(TransformSystem::TransformPropagate),
+        );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let tick_rate = app
+            .world()
+            .get_resource::<LaunchOptions>()
+            .and_then(|options| options.tick_rate)
+            .unwrap_or(DEFAULT_TICK_RATE);
+        app.insert_resource(Time::<Fixed>::from_hz(tick_rate));
+    }
+}
@@ -0,0 +1,260 @@
+use bevy::{
+    app::{App, Last, Plugin, Update},
+    color::{Alpha, Color},
+    core::Name,
+    input::ButtonInput,
+    prelude::{
+        default, not, AppExtStates, BuildChildren, Commands, ComputedStates, IntoSystemConfigs,
+        NextState, NodeBundle, OnEnter, OnExit, Res, ResMut, Resource, State, StateScoped, States,
+        TextBundle,
+    },
+    reflect::Reflect,
+    text::TextStyle,
+    time::{Time, Timer, TimerMode, Virtual},
+    ui::{AlignItems, BackgroundColor, FlexDirection, JustifyContent, Style, Val, ZIndex},
+};
+
+use crate::{
+    actions::{Action, HeldActions},
+    config::LaunchOptions,
+    player::PlayerIndex,
+};
+
+/// seconds before the game over screen takes input, so players still firing do not skip it
+const GAME_OVER_DELAY: f32 = 1.0;
+
+#[derive(States, Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum GameState {
+    #[default]
+    MainMenu,
+    Playing,
+    Paused,
+    GameOver,
+    /// goes straight back to `Playing`, so the current run ends and a new one starts
+    Restarting,
+    /// between two rounds of a match, see [`GameMode::is_match`]
+    RoundOver,
+}
+
+/// Kind of run started from the menu
+#[derive(Resource, Reflect, Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum GameMode {
+    /// a single cannon against the waves
+    #[default]
+    Arcade,
+    /// one cannon per player against the waves, sharing the keyboard or with a gamepad each
+    Coop,
+    /// two players firing at each other from both ends of the arena
+    Versus,
+    /// classic rock paper scissors between two players, picking their hands in secret
+    Duel,
+    /// classic rock paper scissors against the computer
+    DuelCpu,
+}
+
+impl GameMode {
+    /// number of human players in a run
+    #[must_use]
+    pub const fn players(self) -> usize {
+        match self {
+            Self::Arcade | Self::DuelCpu => 1,
+            Self::Coop | Self::Versus | Self::Duel => 2,
+        }
+    }
+
+    /// players play against each other over several rounds instead of against the waves
+    #[must_use]
+    pub const fn is_match(self) -> bool {
+        matches!(self, Self::Versus | Self::Duel | Self::DuelCpu)
+    }
+
+    /// every player controls a cannon, as opposed to duels
+    #[must_use]
+    pub const fn has_cannons(self) -> bool {
+        !matches!(self, Self::Duel | Self::DuelCpu)
+    }
+
+    /// the second player is played by the computer
+    #[must_use]
+    pub const fn against_cpu(self) -> bool {
+        matches!(self, Self::DuelCpu)
+    }
+
+    /// name of a player on screen
+    #[must_use]
+    pub fn player_name(self, player: PlayerIndex) -> String {
+        if self.against_cpu() && player.0 == 1 {
+            "CPU".to_string()
+        } else {
+            format!("P{}", player.0 + 1)
+        }
+    }
+}
+
+/// Run condition, the current mode is played in rounds
+#[must_use]
+pub fn in_match(mode: Res<GameMode>) -> bool {
+    mode.is_match()
+}
+
+/// Run condition, players control cannons in the current mode
+#[must_use]
+pub fn has_cannons(mode: Res<GameMode>) -> bool {
+    mode.has_cannons()
+}
+
+/// Active while a run is in progress, paused or not
+///
+/// Entities scoped to it survive pausing, and are cleaned up when the run ends.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct InGame;
+
+impl ComputedStates for InGame {
+    type SourceStates = GameState;
+
+    fn compute(sources: GameState) -> Option<Self> {
+        matches!(sources, GameState::Playing | GameState::Paused).then_some(Self)
+    }
+}
+
+/// Full screen centered text, removed when leaving `scope`
+pub fn spawn_banner<S: States>(commands: &mut Commands, scope: S, title: &str, subtitle: &str) {
+    commands
+        .spawn((
+            Name::new("Banner"),
+            StateScoped(scope),
+            NodeBundle {
+                background_color: BackgroundColor(Color::BLACK.with_alpha(0.5)),
+                z_index: ZIndex::Global(i32::MAX - 1),
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                title,
+                TextStyle {
+                    font_size: 64.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                subtitle,
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn show_game_over(mut commands: Commands) {
+    spawn_banner(
+        &mut commands,
+        GameState::GameOver,
+        "Game over",
+        "press Enter to play again, Escape for the menu",
+    );
+}
+
+/// Time left before the game over screen takes input
+#[derive(Resource, Debug)]
+struct GameOverDelay(Timer);
+
+impl Default for GameOverDelay {
+    fn default() -> Self {
+        Self(Timer::from_seconds(GAME_OVER_DELAY, TimerMode::Once))
+    }
+}
+
+fn start_game_over_delay(mut delay: ResMut<GameOverDelay>) {
+    delay.0.reset();
+}
+
+fn restart(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Playing);
+}
+
+/// Tweens and particles run on virtual time, freezing it pauses them too
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn change_state(
+    time: Res<Time>,
+    input: Res<ButtonInput<Action>>,
+    state: Res<State<GameState>>,
+    mut delay: ResMut<GameOverDelay>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    // the main menu and pause are handled by the menus
+    if *state.get() != GameState::GameOver || !delay.0.tick(time.delta()).finished() {
+        return;
+    }
+    if input.just_pressed(Action::Confirm) {
+        next_state.set(GameState::Playing);
+    } else if input.just_pressed(Action::Back) {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+/// Buttons still down when leaving a menu, e.g. the fire button also confirming, must be let go
+/// before they act in the game
+fn hold_menu_actions(
+    state: Res<State<GameState>>,
+    next_state: Res<NextState<GameState>>,
+    mut held: ResMut<HeldActions>,
+) {
+    let in_menu = matches!(
+        state.get(),
+        GameState::MainMenu | GameState::Paused | GameState::GameOver
+    );
+    if in_menu && matches!(*next_state, NextState::Pending(_)) {
+        held.hold();
+    }
+}
+
+pub struct StatePlugin;
+
+impl Plugin for StatePlugin {
+    fn build(&self, app: &mut App) {
+        let skip_menu = app
+            .world()
+            .get_resource::<LaunchOptions>()
+            .is_some_and(|options| options.skip_menu);
+        app.register_type::<GameMode>()
+            .init_resource::<GameMode>()
+            .init_resource::<GameOverDelay>()
+            .insert_state(if skip_menu {
+                GameState::Playing
+            } else {
+                GameState::MainMenu
+            })
+            .add_computed_state::<InGame>()
+            .enable_state_scoped_entities::<GameState>()
+            .enable_state_scoped_entities::<InGame>()
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                show_game_over.run_if(not(in_match)),
+            )
+            .add_systems(OnEnter(GameState::Restarting), restart)
+            .add_systems(OnEnter(GameState::Paused), pause_time)
+            .add_systems(OnExit(GameState::Paused), resume_time)
+            .add_systems(OnEnter(GameState::GameOver), start_game_over_delay)
+            .add_systems(Update, change_state)
+            .add_systems(Last, hold_menu_actions);
+    }
+}
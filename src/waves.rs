@@ -0,0 +1,558 @@
+use std::{f32::consts::PI, fmt};
+
+use bevy::{
+    app::{App, FixedUpdate, Plugin, Update},
+    asset::{
+        io::Reader, Asset, AssetApp, AssetEvent, AssetLoader, AssetServer, Assets, AsyncReadExt,
+        Handle,
+    },
+    core::Name,
+    log::warn,
+    math::{Quat, Vec2, Vec3},
+    prelude::{
+        not, Commands, Component, Event, EventReader, EventWriter, FromWorld, IntoSystemConfigs,
+        OnEnter, Query, Res, ResMut, Resource, StateScoped, With, World,
+    },
+    reflect::{Reflect, TypePath},
+    time::{Time, Timer, TimerMode},
+};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    animations::AnimatableSpriteBundle,
+    arena::Arena,
+    combat::Team,
+    entity_gc::{DespawnOutsideBounds, EntityLifetime},
+    fire_pattern::FirePattern,
+    hand::{Hand, HandAnimations, HandBundle},
+    hand_rules::{ActiveHandRules, HandRuleSet, HandRules},
+    movement::Velocity,
+    rng::{GameRng, RngStream},
+    simulation::{Interpolated, SimulationSystems},
+    state::{in_match, InGame},
+    weapon::{AutoFire, Emitter, WeaponSystems},
+};
+
+pub const DEFAULT_WAVES: &str = "waves/default.waves.ron";
+
+/// Which hand each member of a formation gets
+#[derive(Deserialize, Debug, Clone)]
+pub enum HandChoice {
+    Random,
+    /// hand name from the active ruleset
+    Named(String),
+    /// every hand type in turn, along the formation
+    Cycle,
+}
+
+/// Shape of a group of enemies, centered on the spawn point
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum Formation {
+    /// side by side
+    Line { count: u32, spacing: f32 },
+    /// one behind the other, the first one leading
+    Column { count: u32, spacing: f32 },
+    /// pointing down, the first one leading
+    Vee { count: u32, spacing: f32 },
+    Grid {
+        columns: u32,
+        rows: u32,
+        spacing: f32,
+    },
+}
+
+impl Formation {
+    fn offsets(self) -> Vec<Vec2> {
+        let centered = |i: u32, count: u32| i as f32 - (count - 1) as f32 / 2.0;
+        match self {
+            Self::Line { count, spacing } => (0..count)
+                .map(|i| Vec2::new(centered(i, count) * spacing, 0.0))
+                .collect(),
+            Self::Column { count, spacing } => (0..count)
+                .map(|i| Vec2::new(0.0, i as f32 * spacing))
+                .collect(),
+            Self::Vee { count, spacing } => (0..count)
+                .map(|i| {
+                    let rank = i.div_ceil(2) as f32;
+                    let side = if i % 2 == 0 { 1.0 } else { -1.0 };
+                    Vec2::new(side * rank * spacing, rank * spacing)
+                })
+                .collect(),
+            Self::Grid {
+                columns,
+                rows,
+                spacing,
+            } => (0..columns * rows)
+                .map(|i| {
+                    Vec2::new(
+                        centered(i % columns, columns) * spacing,
+                        (i / columns) as f32 * spacing,
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A formation entering the screen at a given time of its wave
+#[derive(Deserialize, Debug, Clone)]
+pub struct WaveSpawn {
+    /// seconds since the start of the wave
+    at: f32,
+    /// horizontal position of the formation center
+    #[serde(default)]
+    x: f32,
+    formation: Formation,
+    hand: HandChoice,
+    /// descent speed, in pixels per second
+    speed: f32,
+    /// weapon every member of the formation shoots with
+    #[serde(default)]
+    emitter: Option<Emitter>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Wave {
+    spawns: Vec<WaveSpawn>,
+    /// seconds of calm once the wave is cleared
+    #[serde(default = "Wave::default_pause")]
+    pause: f32,
+}
+
+impl Wave {
+    const fn default_pause() -> f32 {
+        2.0
+    }
+}
+
+/// Timed sequences of formations, as written in `*.waves.ron` files
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct WaveSet {
+    waves: Vec<Wave>,
+}
+
+impl WaveSet {
+    /// waves of a `*.waves.ron` file, with the spawns of each wave in order
+    fn parse(bytes: &[u8]) -> Result<Self, WaveSetError> {
+        let mut wave_set: Self = ron::de::from_bytes(bytes)?;
+        if wave_set.waves.iter().all(|wave| wave.spawns.is_empty()) {
+            return Err(WaveSetError::Empty);
+        }
+        let emitters = wave_set
+            .waves
+            .iter()
+            .flat_map(|wave| &wave.spawns)
+            .filter_map(|spawn| spawn.emitter.as_ref());
+        for emitter in emitters {
+            if let Some((setting, value)) = emitter_settings(emitter)
+                .into_iter()
+                .find(|(_, value)| value.is_nan() || *value < 0.0)
+            {
+                return Err(WaveSetError::Invalid { setting, value });
+            }
+        }
+        for wave in &mut wave_set.waves {
+            wave.spawns.sort_by(|a, b| a.at.total_cmp(&b.at));
+        }
+        Ok(wave_set)
+    }
+
+    /// hand names used by the waves that `rules` do not define
+    fn unknown_hands<'a>(&'a self, rules: &'a HandRules) -> impl Iterator<Item = &'a str> {
+        self.waves
+            .iter()
+            .flat_map(|wave| &wave.spawns)
+            .filter_map(|spawn| match &spawn.hand {
+                HandChoice::Named(name) if rules.find(name).is_none() => Some(name.as_str()),
+                _ => None,
+            })
+    }
+}
+
+/// settings of an emitter that can't be negative, named as in the waves files
+fn emitter_settings(emitter: &Emitter) -> Vec<(&'static str, f32)> {
+    let mut settings = vec![("cooldown", emitter.cooldown), ("speed", emitter.speed)];
+    match emitter.pattern {
+        FirePattern::Fan { angle, .. } => settings.push(("angle", angle)),
+        FirePattern::Burst { interval, .. } => settings.push(("interval", interval)),
+        FirePattern::Shotgun {
+            angle,
+            speed_jitter,
+            ..
+        } => settings.extend([("angle", angle), ("speed_jitter", speed_jitter)]),
+        FirePattern::Single | FirePattern::Spiral { .. } => {}
+    }
+    settings
+}
+
+#[derive(Debug)]
+pub enum WaveSetError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    Empty,
+    /// a cooldown, speed, spread or jitter below zero or not a number, which the weapons can't
+    /// handle
+    Invalid {
+        setting: &'static str,
+        value: f32,
+    },
+}
+
+impl fmt::Display for WaveSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read waves: {err}"),
+            Self::Ron(err) => write!(f, "could not parse waves: {err}"),
+            Self::Empty => write!(f, "need at least one wave with one spawn"),
+            Self::Invalid { setting, value } => {
+                write!(f, "emitter {setting} must be zero or more, got {value}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for WaveSetError {}
+
+impl From<std::io::Error> for WaveSetError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for WaveSetError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        Self::Ron(err)
+    }
+}
+
+#[derive(Default)]
+struct WaveSetLoader;
+
+impl AssetLoader for WaveSetLoader {
+    type Asset = WaveSet;
+    type Settings = ();
+    type Error = WaveSetError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut bevy::asset::LoadContext<'_>,
+    ) -> Result<WaveSet, WaveSetError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        WaveSet::parse(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["waves.ron"]
+    }
+}
+
+/// Sent when the first formation of a wave enters the screen
+#[derive(Event, Debug, Clone, Copy)]
+pub struct WaveStarted {
+    /// number of waves started before this one, across loops
+    pub wave: usize,
+}
+
+/// Sent when every enemy of a wave has been destroyed or has left
+#[derive(Event, Debug, Clone, Copy)]
+pub struct WaveCleared {
+    pub wave: usize,
+}
+
+/// Marker for enemies spawned by the wave spawner
+#[derive(Component, Reflect)]
+pub struct WaveMember;
+
+#[derive(Debug)]
+enum WavePhase {
+    /// waiting before the next wave starts
+    Intermission(Timer),
+    /// formations of the wave are still entering
+    Spawning { elapsed: f32, next: usize },
+    /// everything is spawned, waiting for the last enemy to go
+    Clearing,
+}
+
+#[derive(Resource, Debug)]
+pub struct WaveSpawner {
+    waves: Handle<WaveSet>,
+    /// total number of waves started
+    wave: usize,
+    phase: WavePhase,
+}
+
+impl WaveSpawner {
+    /// seconds before the first wave of a run
+    const WARMUP: f32 = 1.0;
+
+    fn restart(&mut self) {
+        self.wave = 0;
+        self.phase = WavePhase::Intermission(Timer::from_seconds(Self::WARMUP, TimerMode::Once));
+    }
+}
+
+impl FromWorld for WaveSpawner {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            waves: world.resource::<AssetServer>().load(DEFAULT_WAVES),
+            wave: 0,
+            phase: WavePhase::Intermission(Timer::from_seconds(Self::WARMUP, TimerMode::Once)),
+        }
+    }
+}
+
+/// Hand names can only be checked once both the waves and the rules they are played with are
+/// loaded, spawns fall back to the first hand of the rules
+fn check_hand_names(
+    mut wave_events: EventReader<AssetEvent<WaveSet>>,
+    mut rule_events: EventReader<AssetEvent<HandRuleSet>>,
+    spawner: Res<WaveSpawner>,
+    wave_sets: Res<Assets<WaveSet>>,
+    active: Res<ActiveHandRules>,
+    rule_sets: Res<Assets<HandRuleSet>>,
+) {
+    let waves_loaded = wave_events.read().any(|event| {
+        event.is_loaded_with_dependencies(&spawner.waves) || event.is_modified(&spawner.waves)
+    });
+    let rules_loaded = rule_events.read().any(|event| {
+        event.is_loaded_with_dependencies(active.id()) || event.is_modified(active.id())
+    });
+    if !(waves_loaded || rules_loaded) {
+        return;
+    }
+    let (Some(wave_set), Some(rule_set)) =
+        (wave_sets.get(&spawner.waves), rule_sets.get(active.id()))
+    else {
+        return;
+    };
+    for name in wave_set.unknown_hands(&rule_set.rules) {
+        warn!(
+            "waves use hand {name}, which the active rules do not define, spawning {} instead",
+            rule_set.rules.name(Hand::default())
+        );
+    }
+}
+
+fn restart_waves(mut spawner: ResMut<WaveSpawner>) {
+    spawner.restart();
+}
+
+/// every time the wave set loops, enemies get this much faster
+const LOOP_SPEEDUP: f32 = 0.15;
+const ENEMY_SCALE: f32 = 4.0;
+const ENEMY_PROJECTILE_SCALE: f32 = 2.5;
+/// how far above the top of the arena formations appear
+const SPAWN_MARGIN: f32 = 100.0;
+const ENEMY_LIFETIME: f32 = 15.0;
+
+#[allow(clippy::too_many_arguments)]
+fn run_waves(
+    time: Res<Time>,
+    mut spawner: ResMut<WaveSpawner>,
+    wave_sets: Res<Assets<WaveSet>>,
+    members: Query<(), With<WaveMember>>,
+    arena: Res<Arena>,
+    rules: Res<HandRules>,
+    hand_animations: Res<HandAnimations>,
+    mut rng: ResMut<GameRng>,
+    mut started: EventWriter<WaveStarted>,
+    mut cleared: EventWriter<WaveCleared>,
+    mut commands: Commands,
+) {
+    let Some(wave_set) = wave_sets.get(&spawner.waves) else {
+        return;
+    };
+    let current = spawner.wave;
+    let wave = &wave_set.waves[current % wave_set.waves.len()];
+    let speedup = LOOP_SPEEDUP.mul_add((current / wave_set.waves.len()) as f32, 1.0);
+
+    match &mut spawner.phase {
+        WavePhase::Intermission(timer) => {
+            if timer.tick(time.delta()).finished() {
+                started.send(WaveStarted { wave: current });
+                spawner.phase = WavePhase::Spawning {
+                    elapsed: 0.0,
+                    next: 0,
+                };
+            }
+        }
+        WavePhase::Spawning { elapsed, next } => {
+            *elapsed += time.delta_seconds();
+            let top = arena.rect.max.y + SPAWN_MARGIN;
+            while let Some(spawn) = wave.spawns.get(*next).filter(|s| s.at <= *elapsed) {
+                spawn_formation(
+                    &mut commands,
+                    spawn,
+                    Vec3::new(spawn.x, top, 0.0),
+                    spawn.speed * speedup,
+                    &rules,
+                    &hand_animations,
+                    rng.stream(RngStream::Waves),
+                );
+                *next += 1;
+            }
+            // members spawned this frame are not queryable yet, check for clearing next frame
+            if *next >= wave.spawns.len() {
+                spawner.phase = WavePhase::Clearing;
+            }
+        }
+        WavePhase::Clearing => {
+            if members.is_empty() {
+                cleared.send(WaveCleared { wave: current });
+                spawner.wave += 1;
+                spawner.phase =
+                    WavePhase::Intermission(Timer::from_seconds(wave.pause, TimerMode::Once));
+            }
+        }
+    }
+}
+
+fn spawn_formation(
+    commands: &mut Commands,
+    spawn: &WaveSpawn,
+    center: Vec3,
+    speed: f32,
+    rules: &HandRules,
+    hand_animations: &HandAnimations,
+    rng: &mut impl Rng,
+) {
+    for (i, offset) in spawn.formation.offsets().into_iter().enumerate() {
+        let hand = match &spawn.hand {
+            HandChoice::Random => rules.random(rng),
+            // unknown names are reported by `check_hand_names`
+            HandChoice::Named(name) => rules.find(name).unwrap_or_default(),
+            HandChoice::Cycle => Hand::new(i % rules.count()),
+        };
+        let mut sprite = AnimatableSpriteBundle::new(
+            center + offset.extend(0.0),
+            Vec3::splat(ENEMY_SCALE),
+            hand_animations.get(hand),
+            hand_animations.layout(),
+            hand_animations.indices(),
+            0.25,
+        );
+        // enemies point down, towards the player
+        sprite.sprite.transform.rotation = Quat::from_rotation_z(PI);
+        let mut enemy = commands.spawn((
+            Name::new("Enemy hand"),
+            StateScoped(InGame),
+            HandBundle { hand, sprite },
+            Velocity::new(0.0, -speed, 0.0),
+            Team::Enemy,
+            WaveMember,
+            EntityLifetime::new(ENEMY_LIFETIME),
+            DespawnOutsideBounds::arena(SPAWN_MARGIN),
+            Interpolated::default(),
+        ));
+        if let Some(emitter) = &spawn.emitter {
+            enemy.insert((emitter.weapon(ENEMY_PROJECTILE_SCALE), AutoFire));
+        }
+    }
+}
+
+pub struct WavesPlugin;
+
+impl Plugin for WavesPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<WaveMember>()
+            .init_asset::<WaveSet>()
+            .init_asset_loader::<WaveSetLoader>()
+            .init_resource::<WaveSpawner>()
+            .add_event::<WaveStarted>()
+            .add_event::<WaveCleared>()
+            .add_systems(OnEnter(InGame), restart_waves)
+            .add_systems(Update, check_hand_names)
+            .add_systems(
+                FixedUpdate,
+                run_waves
+                    .run_if(not(in_match))
+                    .in_set(SimulationSystems::Spawn)
+                    .before(WeaponSystems::Trigger),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a single spawn shooting `pattern` with the given settings
+    fn parse_emitter(pattern: &str, cooldown: f32, speed: f32) -> Result<WaveSet, WaveSetError> {
+        let source = format!(
+            "(waves: [(spawns: [(at: 0.0, formation: Line(count: 1, spacing: 0.0), hand: Random, \
+             speed: 100.0, emitter: Some((pattern: {pattern}, cooldown: {cooldown:?}, \
+             speed: {speed:?})))])])"
+        );
+        WaveSet::parse(source.as_bytes())
+    }
+
+    fn assert_invalid(result: &Result<WaveSet, WaveSetError>, name: &str) {
+        assert!(
+            matches!(result, Err(WaveSetError::Invalid { setting, .. }) if *setting == name),
+            "{name}: {result:?}"
+        );
+    }
+
+    #[test]
+    fn default_waves_are_valid() {
+        WaveSet::parse(include_bytes!("../assets/waves/default.waves.ron")).unwrap();
+    }
+
+    #[test]
+    fn accepts_zero_spreads() {
+        parse_emitter(
+            "Shotgun(count: 3, angle: 0.0, speed_jitter: 0.0)",
+            1.0,
+            200.0,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn rejects_negative_settings() {
+        assert_invalid(&parse_emitter("Single", -1.0, 200.0), "cooldown");
+        assert_invalid(&parse_emitter("Single", 1.0, -200.0), "speed");
+        assert_invalid(
+            &parse_emitter("Fan(count: 3, angle: -30.0)", 1.0, 200.0),
+            "angle",
+        );
+        assert_invalid(
+            &parse_emitter("Burst(count: 3, interval: -0.1)", 1.0, 200.0),
+            "interval",
+        );
+        assert_invalid(
+            &parse_emitter(
+                "Shotgun(count: 3, angle: 30.0, speed_jitter: -0.2)",
+                1.0,
+                200.0,
+            ),
+            "speed_jitter",
+        );
+    }
+
+    #[test]
+    fn rejects_nan_settings() {
+        assert_invalid(&parse_emitter("Single", f32::NAN, 200.0), "cooldown");
+        assert_invalid(&parse_emitter("Single", 1.0, f32::NAN), "speed");
+        assert_invalid(
+            &parse_emitter(
+                "Shotgun(count: 3, angle: NaN, speed_jitter: 0.2)",
+                1.0,
+                200.0,
+            ),
+            "angle",
+        );
+    }
+
+    #[test]
+    fn rejects_waves_without_spawns() {
+        assert!(matches!(
+            WaveSet::parse(b"(waves: [(spawns: [])])"),
+            Err(WaveSetError::Empty)
+        ));
+    }
+}
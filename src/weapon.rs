@@ -0,0 +1,268 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{App, FixedUpdate, Plugin},
+    core::Name,
+    math::{Vec2, Vec3},
+    prelude::{
+        Bundle, Commands, Component, IntoSystemConfigs, IntoSystemSetConfigs, Query, Res, ResMut,
+        StateScoped, SystemSet, Transform, With,
+    },
+    reflect::Reflect,
+    time::{Time, Timer, TimerMode},
+};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    animations::AnimatableSpriteBundle,
+    combat::Team,
+    entity_gc::{DespawnOutsideBounds, EntityLifetime},
+    fire_pattern::{FirePattern, FirePatternState, Shot},
+    hand::{Hand, HandAnimations, HandBundle},
+    movement::Velocity,
+    player::{Owner, PlayerIndex},
+    pool::HandPool,
+    rng::{GameRng, RngStream},
+    simulation::{Interpolated, SimulationSystems},
+    state::InGame,
+};
+
+/// Firing stats of anything shooting hands
+#[derive(Component, Reflect, Debug, Clone)]
+pub struct Weapon {
+    cooldown: Timer,
+    /// pixels per second
+    pub projectile_speed: f32,
+    /// seconds before a projectile is despawned
+    pub projectile_lifetime: f32,
+    /// maximum deviation of a shot from the aim direction, in radians
+    pub spread: f32,
+    /// projectile sprite scale
+    pub scale: f32,
+}
+
+impl Weapon {
+    /// `cooldown` is the number of seconds between two shots
+    #[must_use]
+    pub fn new(cooldown: f32) -> Self {
+        let mut cooldown = Timer::from_seconds(cooldown, TimerMode::Once);
+        // ready to fire right away
+        cooldown.tick(Duration::MAX);
+        Self {
+            cooldown,
+            projectile_speed: 1000.0,
+            projectile_lifetime: 5.0,
+            spread: 0.0,
+            scale: 6.0,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_spread(mut self, spread: f32) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    #[must_use]
+    pub fn ready(&self) -> bool {
+        self.cooldown.finished()
+    }
+
+    /// starts the cooldown, the weapon won't be [`ready`](Self::ready) until it is over
+    pub fn trigger(&mut self) {
+        self.cooldown.reset();
+    }
+}
+
+impl Default for Weapon {
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
+
+/// Shots of past trigger pulls that did not leave the weapon yet
+#[derive(Component, Reflect, Debug, Default)]
+pub struct Volley {
+    shots: Vec<(Hand, Shot)>,
+}
+
+impl Volley {
+    pub fn load(&mut self, hand: Hand, shots: impl IntoIterator<Item = Shot>) {
+        self.shots
+            .extend(shots.into_iter().map(|shot| (hand, shot)));
+    }
+}
+
+#[derive(Bundle, Default)]
+pub struct WeaponBundle {
+    pub weapon: Weapon,
+    pub pattern: FirePattern,
+    pub pattern_state: FirePatternState,
+    pub volley: Volley,
+}
+
+impl WeaponBundle {
+    #[must_use]
+    pub fn new(weapon: Weapon, pattern: FirePattern) -> Self {
+        Self {
+            weapon,
+            pattern,
+            ..Default::default()
+        }
+    }
+}
+
+/// Pulls the trigger of a [`Weapon`], loaded with its own [`Hand`], as soon as it is ready
+#[derive(Component, Reflect, Debug)]
+pub struct AutoFire;
+
+/// Weapon carried by an enemy, as written in `*.waves.ron` files
+#[derive(Deserialize, Debug, Clone)]
+pub struct Emitter {
+    pub pattern: FirePattern,
+    /// seconds between two trigger pulls
+    pub cooldown: f32,
+    /// pixels per second
+    pub speed: f32,
+}
+
+impl Emitter {
+    #[must_use]
+    pub fn weapon(&self, scale: f32) -> WeaponBundle {
+        let mut weapon = Weapon::new(self.cooldown);
+        weapon.projectile_speed = self.speed;
+        weapon.scale = scale;
+        WeaponBundle::new(weapon, self.pattern.clone())
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WeaponSystems {
+    /// weapons load their volleys
+    Trigger,
+    /// loaded shots leave the weapons
+    Release,
+}
+
+/// runs in [`WeaponSystems::Trigger`], triggers must run after it to see the weapons ready
+pub(crate) fn tick_weapons(time: Res<Time>, mut query: Query<&mut Weapon>) {
+    for mut weapon in &mut query {
+        weapon.cooldown.tick(time.delta());
+    }
+}
+
+fn auto_fire(
+    mut query: Query<
+        (
+            &Hand,
+            &mut Weapon,
+            &FirePattern,
+            &mut FirePatternState,
+            &mut Volley,
+        ),
+        With<AutoFire>,
+    >,
+    mut rng: ResMut<GameRng>,
+) {
+    let rng = rng.stream(RngStream::EnemyFire);
+    for (hand, mut weapon, pattern, mut pattern_state, mut volley) in &mut query {
+        if weapon.ready() {
+            weapon.trigger();
+            volley.load(*hand, pattern.shots(&mut pattern_state, rng));
+        }
+    }
+}
+
+const PROJECTILE_FRAME_TIME: f32 = 0.25;
+/// how far off-screen projectiles go before being despawned
+const PROJECTILE_CULL_MARGIN: f32 = 50.0;
+
+fn release_volleys(
+    time: Res<Time>,
+    mut query: Query<(
+        &Transform,
+        &Weapon,
+        &Team,
+        Option<&PlayerIndex>,
+        &mut Volley,
+    )>,
+    hand_animations: Res<HandAnimations>,
+    mut pool: ResMut<HandPool>,
+    mut rng: ResMut<GameRng>,
+    mut commands: Commands,
+) {
+    let rng = rng.stream(RngStream::Spread);
+    for (transform, weapon, team, player, mut volley) in &mut query {
+        let aim = (transform.rotation * Vec3::Y)
+            .truncate()
+            .normalize_or(Vec2::Y);
+        volley.shots.retain_mut(|(hand, shot)| {
+            shot.delay -= time.delta_seconds();
+            if shot.delay > 0.0 {
+                return true;
+            }
+            let deviation = if weapon.spread > 0.0 {
+                rng.gen_range(-weapon.spread..=weapon.spread)
+            } else {
+                0.0
+            };
+            let direction = Vec2::from_angle(shot.angle + deviation).rotate(aim);
+            let speed = weapon.projectile_speed * shot.speed_factor;
+            let mut sprite = AnimatableSpriteBundle::new(
+                transform.translation,
+                Vec3::splat(weapon.scale),
+                hand_animations.get(*hand),
+                hand_animations.layout(),
+                hand_animations.indices(),
+                PROJECTILE_FRAME_TIME,
+            );
+            sprite.sprite.transform.rotation = transform.rotation;
+            pool.spawn(
+                &mut commands,
+                (
+                    Name::new("Hand"),
+                    StateScoped(InGame),
+                    EntityLifetime::new(weapon.projectile_lifetime),
+                    DespawnOutsideBounds::viewport(PROJECTILE_CULL_MARGIN),
+                    Interpolated::default(),
+                    HandBundle {
+                        hand: *hand,
+                        sprite,
+                    },
+                    Velocity::from((direction * speed).extend(0.0)),
+                    *team,
+                    Owner(player.copied()),
+                ),
+            );
+            false
+        });
+    }
+}
+
+pub struct WeaponPlugin;
+
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Weapon>()
+            .register_type::<FirePattern>()
+            .register_type::<FirePatternState>()
+            .register_type::<Volley>()
+            .register_type::<AutoFire>()
+            .configure_sets(
+                FixedUpdate,
+                (WeaponSystems::Trigger, WeaponSystems::Release)
+                    .chain()
+                    .in_set(SimulationSystems::Spawn),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    (tick_weapons, auto_fire)
+                        .chain()
+                        .in_set(WeaponSystems::Trigger),
+                    release_volleys.in_set(WeaponSystems::Release),
+                ),
+            );
+    }
+}
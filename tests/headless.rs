@@ -0,0 +1,174 @@
+use std::{collections::HashMap, time::Duration};
+
+use bevy::{
+    app::{App, Last, PreUpdate},
+    input::{keyboard::KeyCode, ButtonInput, InputSystem},
+    prelude::{
+        Entity, EventReader, IntoSystemConfigs, Query, Res, ResMut, Resource, State, Transform,
+        With, Without,
+    },
+    time::TimeUpdateStrategy,
+};
+use rps_game::{
+    actions::{Action, ActionSystems, PlayerActions},
+    combat::HandClash,
+    hand_cannon::HandCannon,
+    player::{Owner, PlayerIndex},
+    state::GameState,
+    Hand, HeadlessPlugin, LaunchOptions, RpsGamePlugins,
+};
+
+/// frame the menu is confirmed on, once the first menu is up
+const CONFIRM_FRAME: u32 = 5;
+/// frames the run lasts at most, a minute of game time
+const MAX_FRAMES: u32 = 3600;
+/// frame the key confirming the menu is let go
+const RELEASE_FRAME: u32 = 60;
+/// frame the same key is pressed again, to fire
+const PRESS_AGAIN_FRAME: u32 = 70;
+
+/// Frames played so far and what the game did during them
+#[derive(Resource, Default)]
+struct Run {
+    frame: u32,
+    positions: HashMap<Entity, Transform>,
+    spawned: bool,
+    moved: bool,
+    clashes: usize,
+    /// frame the first hand fired by a player showed up
+    first_shot: Option<u32>,
+}
+
+impl Run {
+    const fn done(&self) -> bool {
+        self.spawned && self.moved && self.clashes > 0
+    }
+}
+
+/// Confirms "Play" in the main menu, then holds fire, in place of the keyboard
+fn script_actions(run: Res<Run>, mut players: ResMut<PlayerActions>) {
+    let Some(actions) = players.get_mut(PlayerIndex(0)) else {
+        return;
+    };
+    if run.frame == CONFIRM_FRAME {
+        actions.press(Action::Confirm);
+    } else if run.frame > CONFIRM_FRAME {
+        actions.press(Action::Fire);
+    }
+}
+
+/// Confirms "Play" with Space, which also fires, and keeps holding it for a while
+fn hold_space(run: Res<Run>, mut keys: ResMut<ButtonInput<KeyCode>>) {
+    match run.frame {
+        CONFIRM_FRAME | PRESS_AGAIN_FRAME => keys.press(KeyCode::Space),
+        RELEASE_FRAME => keys.release(KeyCode::Space),
+        _ => {}
+    }
+}
+
+/// hands fired or spawned by the waves, not the cannons holding them
+type FlyingHands = (With<Hand>, Without<HandCannon>);
+
+fn watch_hands(
+    hands: Query<(Entity, &Transform, Option<&Owner>), FlyingHands>,
+    mut clashes: EventReader<HandClash>,
+    mut run: ResMut<Run>,
+) {
+    run.frame += 1;
+    run.clashes += clashes.read().count();
+    for (entity, transform, owner) in &hands {
+        run.spawned = true;
+        if owner.is_some_and(|owner| owner.0.is_some()) && run.first_shot.is_none() {
+            run.first_shot = Some(run.frame);
+        }
+        let previous = run.positions.insert(entity, *transform);
+        if previous.is_some_and(|previous| previous.translation != transform.translation) {
+            run.moved = true;
+        }
+    }
+}
+
+fn app<M>(options: LaunchOptions, script: impl IntoSystemConfigs<M>) -> App {
+    let mut app = App::new();
+    app.add_plugins(HeadlessPlugin)
+        .insert_resource(LaunchOptions {
+            seed: Some(42),
+            ..options
+        })
+        .add_plugins(RpsGamePlugins)
+        .insert_resource(TimeUpdateStrategy::ManualDuration(
+            Duration::from_secs(1) / 60,
+        ))
+        .init_resource::<Run>()
+        .add_systems(PreUpdate, script)
+        .add_systems(Last, watch_hands);
+    // done by `App::run` otherwise
+    app.finish();
+    app.cleanup();
+    app
+}
+
+fn play(app: &mut App) -> GameState {
+    while app.world().resource::<Run>().frame < MAX_FRAMES && !app.world().resource::<Run>().done()
+    {
+        app.update();
+    }
+    *app.world().resource::<State<GameState>>().get()
+}
+
+/// actions replacing those of the keyboard
+fn scripted_app(options: LaunchOptions) -> App {
+    app(
+        options,
+        script_actions
+            .after(ActionSystems::Read)
+            .before(ActionSystems::Merge),
+    )
+}
+
+#[test]
+fn hands_spawn_move_and_clash() {
+    let mut app = scripted_app(LaunchOptions::default());
+    let state = play(&mut app);
+
+    let run = app.world().resource::<Run>();
+    assert_eq!(state, GameState::Playing);
+    assert!(run.spawned, "no hand spawned");
+    assert!(run.moved, "no hand moved");
+    assert!(run.clashes > 0, "no hands clashed");
+}
+
+#[test]
+fn skip_menu_starts_playing() {
+    let mut app = scripted_app(LaunchOptions {
+        skip_menu: true,
+        ..LaunchOptions::default()
+    });
+    app.update();
+
+    assert_eq!(
+        *app.world().resource::<State<GameState>>().get(),
+        GameState::Playing
+    );
+}
+
+#[test]
+fn key_confirming_the_menu_fires_once_pressed_again() {
+    let mut app = app(
+        LaunchOptions::default(),
+        hold_space.after(InputSystem).before(ActionSystems::Read),
+    );
+    while app.world().resource::<Run>().frame < PRESS_AGAIN_FRAME + 30 {
+        app.update();
+    }
+
+    let first_shot = app.world().resource::<Run>().first_shot;
+    assert_eq!(
+        *app.world().resource::<State<GameState>>().get(),
+        GameState::Playing
+    );
+    assert!(
+        first_shot.is_some_and(|frame| frame > PRESS_AGAIN_FRAME),
+        "first shot on frame {first_shot:?}"
+    );
+}